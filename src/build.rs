@@ -0,0 +1,59 @@
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::{BuildConfig, Settings};
+use crate::locale::validate_locales;
+use crate::locale_refs::validate_locale_references;
+use crate::mod_info::validate_info;
+use crate::workspace::{select_mods, WorkspaceMod};
+use crate::zip_builder::build_zip;
+
+/// A mod zipped into its output directory
+pub struct BuiltMod {
+    pub module: WorkspaceMod,
+    pub zip_path: PathBuf,
+}
+
+/// Resolve the selected mods, order them by dependency and zip each into its output directory
+pub fn build_mods(mod_path: Option<PathBuf>, settings: &Settings) -> Result<Vec<BuiltMod>> {
+    let mods = select_mods(mod_path, settings)?;
+
+    let mut built = Vec::with_capacity(mods.len());
+    for m in mods {
+        settings.log(&format!("🔍 Processing mod at {}", m.root.display()));
+        let config = settings.build_config(&m.info.name);
+        check_sources(&m, &config, settings)?;
+
+        let zip_name = m.info.zip_name();
+        fs::create_dir_all(&config.out_dir)?;
+        let zip_path = config.out_dir.join(format!("{}.zip", zip_name));
+
+        build_zip(&m.root, &zip_path, &zip_name, &config)?;
+        built.push(BuiltMod { module: m, zip_path });
+    }
+
+    Ok(built)
+}
+
+/// Stop before zipping a mod whose info.json or locale files the game would reject; summarize softer problems
+fn check_sources(m: &WorkspaceMod, config: &BuildConfig, settings: &Settings) -> Result<()> {
+    let mut diagnostics = validate_info(&m.root)?;
+    diagnostics.extend(validate_locales(&m.root)?);
+    diagnostics.extend(validate_locale_references(&m.root, &m.info.name, config)?);
+    let (errors, warnings): (Vec<_>, Vec<_>) = diagnostics.iter().partition(|d| d.is_error());
+
+    for warning in &warnings {
+        settings.log(&warning.to_string());
+    }
+    if !warnings.is_empty() && !settings.verbose {
+        eprintln!("⚠️  {} warning(s) in {}; run `cargo factorio check` for details", warnings.len(), m.info.name);
+    }
+    if !errors.is_empty() {
+        for error in &errors {
+            eprintln!("{}", error);
+        }
+        bail!("{} has {} error(s) in info.json or its locale files; fix them before building", m.info.name, errors.len());
+    }
+    Ok(())
+}
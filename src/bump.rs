@@ -0,0 +1,175 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::changelog::{CHANGELOG_FILE, CHANGELOG_SEPARATOR};
+use crate::config::Settings;
+use crate::mod_info::Version;
+use crate::time::civil_from_days;
+use crate::workspace::select_mods;
+
+/// How to compute the new version
+#[derive(Clone, Copy)]
+pub enum Bump {
+    Major,
+    Minor,
+    Patch,
+    To(Version),
+}
+
+impl FromStr for Bump {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "major" => Bump::Major,
+            "minor" => Bump::Minor,
+            "patch" => Bump::Patch,
+            _ => Bump::To(s.parse().map_err(|e| anyhow!("expected major, minor, patch or x.y.z: {}", e))?),
+        })
+    }
+}
+
+impl Bump {
+    fn apply(self, current: Version) -> Result<Version> {
+        let next = |part: u16| part.checked_add(1).context("version part would exceed 65535");
+        Ok(match self {
+            Bump::Major => Version { major: next(current.major)?, minor: 0, patch: 0 },
+            Bump::Minor => Version { major: current.major, minor: next(current.minor)?, patch: 0 },
+            Bump::Patch => Version { major: current.major, minor: current.minor, patch: next(current.patch)? },
+            Bump::To(version) => version,
+        })
+    }
+}
+
+/// Set a new version in each selected mod's info.json and open a changelog section for it
+pub fn bump_mods(mod_path: Option<PathBuf>, settings: &Settings, bump: Bump, messages: &[String]) -> Result<()> {
+    for m in select_mods(mod_path, settings)? {
+        let current: Version = m.info.version.parse().with_context(|| format!("Invalid current version in {}", m.root.display()))?;
+        let new = bump.apply(current).with_context(|| format!("Cannot bump {} {}", m.info.name, current))?;
+        if new <= current {
+            bail!("New version {} of {} must be higher than the current {}", new, m.info.name, current);
+        }
+
+        let changelog = m.root.join(CHANGELOG_FILE);
+        let updated_changelog = with_changelog_section(&changelog, new, messages)?;
+
+        set_info_version(&m.root.join("info.json"), &m.info.version, new)?;
+        println!("🔖 {} {} → {}", m.info.name, current, new);
+        if let Some(updated) = updated_changelog {
+            fs::write(&changelog, updated)?;
+            println!("📝 Added {} section to {}", new, changelog.display());
+        }
+    }
+    Ok(())
+}
+
+/// Replace the `"version"` value in info.json, leaving every other byte of the file alone
+fn set_info_version(path: &Path, current: &str, new: Version) -> Result<()> {
+    let source = fs::read_to_string(path)?;
+    let literal = serde_json::to_string(current)?;
+
+    let value_start = source.match_indices("\"version\"").find_map(|(i, key)| {
+        let after_key = &source[i + key.len()..];
+        let after_colon = after_key.trim_start().strip_prefix(':')?;
+        let value = after_colon.trim_start();
+        value.starts_with(&literal).then(|| source.len() - value.len())
+    });
+    let Some(start) = value_start else {
+        bail!("Could not find the version {} in {}", literal, path.display());
+    };
+
+    let updated = format!("{}\"{}\"{}", &source[..start], new, &source[start + literal.len()..]);
+    fs::write(path, updated)?;
+    Ok(())
+}
+
+/// The changelog with a dated `Version:` section for `version` put on top, or None when the top section already is for `version`.
+/// A new section needs at least one message, so the changelog never gets an empty entry.
+fn with_changelog_section(path: &Path, version: Version, messages: &[String]) -> Result<Option<String>> {
+    let existing = if path.exists() { fs::read_to_string(path)? } else { String::new() };
+    let newline = if existing.contains("\r\n") { "\r\n" } else { "\n" };
+
+    let top_version = existing.lines().map(str::trim_end).find_map(|line| line.strip_prefix("Version: "));
+    if top_version == Some(version.to_string().as_str()) {
+        return Ok(None);
+    }
+    if messages.is_empty() {
+        bail!("{} has no section for {} yet; describe the changes with -m \"...\"", path.display(), version);
+    }
+
+    let mut section = vec![CHANGELOG_SEPARATOR.to_string(), format!("Version: {}", version), format!("Date: {}", today())];
+    section.push("  Changes:".to_string());
+    section.extend(messages.iter().map(|m| format!("    - {}", m)));
+    let mut updated = section.join(newline) + newline;
+    updated.push_str(&existing);
+    Ok(Some(updated))
+}
+
+/// Today's date in UTC as YYYY-MM-DD
+fn today() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const V2: Version = Version { major: 2, minor: 0, patch: 0 };
+
+    #[test]
+    fn set_info_version_keeps_the_rest_of_the_file() {
+        let dir = TempDir::new("bump-info");
+        let path = dir.join("info.json");
+        let source = "{\r\n\t\"title\": \"A\",\r\n\t\"version\" :  \"1.0.0\",\r\n\t\"name\": \"a\"\r\n}";
+        fs::write(&path, source).unwrap();
+
+        set_info_version(&path, "1.0.0", V2).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), source.replace("1.0.0", "2.0.0"));
+    }
+
+    #[test]
+    fn set_info_version_skips_other_version_keys() {
+        let dir = TempDir::new("bump-info-keys");
+        let path = dir.join("info.json");
+        fs::write(&path, r#"{"factorio_version": "1.0", "description": "\"version\": \"1.0.0\"", "version": "1.0.0"}"#).unwrap();
+
+        set_info_version(&path, "1.0.0", V2).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            r#"{"factorio_version": "1.0", "description": "\"version\": \"1.0.0\"", "version": "2.0.0"}"#
+        );
+    }
+
+    #[test]
+    fn changelog_section_goes_on_top_with_the_file_line_endings() {
+        let dir = TempDir::new("bump-changelog");
+        let path = dir.join(CHANGELOG_FILE);
+        let existing = format!("{}\r\nVersion: 1.0.0\r\n  Changes:\r\n    - First release.\r\n", CHANGELOG_SEPARATOR);
+        fs::write(&path, &existing).unwrap();
+
+        let updated = with_changelog_section(&path, V2, &["Added planets.".to_string()]).unwrap().unwrap();
+        let expected = format!(
+            "{}\r\nVersion: 2.0.0\r\nDate: {}\r\n  Changes:\r\n    - Added planets.\r\n{}",
+            CHANGELOG_SEPARATOR,
+            today(),
+            existing
+        );
+        assert_eq!(updated, expected);
+    }
+
+    #[test]
+    fn changelog_section_needs_a_message() {
+        let dir = TempDir::new("bump-changelog-empty");
+        let path = dir.join(CHANGELOG_FILE);
+        assert!(with_changelog_section(&path, V2, &[]).is_err());
+
+        fs::write(&path, format!("{}\nVersion: 2.0.0\n  Changes:\n    - Written by hand.\n", CHANGELOG_SEPARATOR)).unwrap();
+        assert!(with_changelog_section(&path, V2, &[]).unwrap().is_none());
+    }
+}
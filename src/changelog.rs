@@ -0,0 +1,400 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Settings;
+use crate::diagnostics::Diagnostic;
+use crate::mod_info::Version;
+use crate::workspace::select_mods;
+
+/// Changelog file the game shows in its mod manager
+pub const CHANGELOG_FILE: &str = "changelog.txt";
+
+/// Line that starts every changelog section: exactly 99 dashes
+pub const CHANGELOG_SEPARATOR: &str =
+    "---------------------------------------------------------------------------------------------------";
+
+/// A parsed changelog.txt, newest section first as in the file
+pub struct Changelog {
+    pub sections: Vec<Section>,
+    /// Some lines could not be placed anywhere, so `to_text` would lose them
+    lossy: bool,
+}
+
+/// How `changelog show` renders a section
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NotesFormat {
+    /// `### Category` headings and `-` lists
+    Markdown,
+    /// Indented plain text, like the in-game changelog
+    Text,
+}
+
+/// Everything under one separator
+pub struct Section {
+    /// Text after `Version:`, kept as written
+    pub version: String,
+    pub date: Option<String>,
+    pub categories: Vec<Category>,
+    /// 1-based line of the `Version:` line (or the separator when it is missing)
+    pub line: usize,
+}
+
+impl Section {
+    /// The section's categories and entries as release notes
+    pub fn render(&self, format: NotesFormat) -> String {
+        let mut out = String::new();
+        for category in &self.categories {
+            match format {
+                NotesFormat::Markdown => {
+                    if !out.is_empty() {
+                        out.push('\n');
+                    }
+                    out.push_str(&format!("### {}\n\n", category.name));
+                }
+                NotesFormat::Text => out.push_str(&format!("{}:\n", category.name)),
+            }
+            let (bullet, continuation) = match format {
+                NotesFormat::Markdown => ("- ", "  "),
+                NotesFormat::Text => ("  - ", "    "),
+            };
+            for entry in &category.entries {
+                for (i, text) in entry.lines().enumerate() {
+                    out.push_str(if i == 0 { bullet } else { continuation });
+                    out.push_str(text);
+                    out.push('\n');
+                }
+            }
+        }
+        out
+    }
+}
+
+/// A `  Name:` block and its entries
+pub struct Category {
+    pub name: String,
+    /// Entry text; continuation lines are joined with `\n`
+    pub entries: Vec<String>,
+}
+
+impl Changelog {
+    /// Parse leniently, reporting every deviation from the format the game accepts
+    pub fn parse(source: &str, file: &Path) -> (Self, Vec<Diagnostic>) {
+        let mut parser = Parser { file, changelog: Changelog { sections: Vec::new(), lossy: false }, diagnostics: Vec::new() };
+        for (i, line) in source.lines().enumerate() {
+            parser.line(i + 1, line);
+        }
+        parser.finish()
+    }
+
+    pub fn section(&self, version: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.version == version)
+    }
+
+    /// The changelog in canonical form: exact separators, two-space categories, four-space entries
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for section in &self.sections {
+            out.push_str(CHANGELOG_SEPARATOR);
+            out.push('\n');
+            out.push_str(&format!("Version: {}\n", section.version));
+            if let Some(date) = &section.date {
+                out.push_str(&format!("Date: {}\n", date));
+            }
+            for category in &section.categories {
+                out.push_str(&format!("  {}:\n", category.name));
+                for entry in &category.entries {
+                    for (i, text) in entry.lines().enumerate() {
+                        out.push_str(if i == 0 { "    - " } else { "      " });
+                        out.push_str(text);
+                        out.push('\n');
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+struct Parser<'a> {
+    file: &'a Path,
+    changelog: Changelog,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Parser<'_> {
+    fn error(&mut self, position: (usize, usize), message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic::error(self.file, position, message));
+    }
+
+    fn line(&mut self, number: usize, raw: &str) {
+        let file = self.file;
+        let diagnostics = &mut self.diagnostics;
+        let mut error = |position: (usize, usize), message: &str| diagnostics.push(Diagnostic::error(file, position, message));
+
+        if let Some(column) = raw.find('\t') {
+            error((number, column + 1), "tabs are not allowed; indent with spaces");
+        }
+        let line = raw.replace('\t', "    ");
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return;
+        }
+        let indent = line.len() - line.trim_start().len();
+
+        if trimmed.len() >= 3 && trimmed.bytes().all(|b| b == b'-') {
+            if line != CHANGELOG_SEPARATOR {
+                error((number, 1), &format!("separator must be exactly 99 dashes with no indentation (found {})", trimmed.len()));
+            }
+            self.changelog.sections.push(Section { version: String::new(), date: None, categories: Vec::new(), line: number });
+            return;
+        }
+
+        let Some(section) = self.changelog.sections.last_mut() else {
+            self.changelog.lossy = true;
+            error((number, 1), "changelog must start with a line of 99 dashes");
+            return;
+        };
+
+        // Deeper-indented lines are entry text, whatever they look like
+        let has_entry = section.categories.last().is_some_and(|c| !c.entries.is_empty());
+        let header = indent < 2 || !has_entry;
+
+        if let Some(rest) = trimmed.strip_prefix("Version:").filter(|_| header) {
+            let version = rest.trim().to_string();
+            if !section.version.is_empty() {
+                self.changelog.lossy = true;
+                error((number, indent + 1), "a section has only one `Version:` line; start a new section with a separator");
+                return;
+            }
+            section.version = version.clone();
+            section.line = number;
+            if indent > 0 || !rest.starts_with(' ') || rest.starts_with("  ") {
+                error((number, 1), "write the version line as `Version: x.y.z` with no indentation");
+            }
+            if let Err(e) = version.parse::<Version>() {
+                error((number, 10), &e.to_string());
+            }
+            return;
+        }
+
+        if section.version.is_empty() {
+            error((number, indent + 1), "expected `Version: x.y.z` right after the separator");
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("Date:").filter(|_| header) {
+            if section.date.is_some() {
+                error((number, indent + 1), "a section has only one `Date:` line");
+            } else if !section.categories.is_empty() {
+                error((number, indent + 1), "`Date:` must come before the first category");
+            }
+            section.date = Some(rest.trim().to_string());
+            if indent > 0 || !rest.starts_with(' ') {
+                error((number, 1), "write the date line as `Date: ...` with no indentation");
+            }
+            return;
+        }
+
+        if let Some(text) = trimmed.strip_prefix('-').filter(|_| indent < 6 || !has_entry) {
+            let Some(category) = section.categories.last_mut() else {
+                self.changelog.lossy = true;
+                error((number, indent + 1), "entry outside of a category; add a category line such as `  Changes:` first");
+                return;
+            };
+            category.entries.push(text.trim().to_string());
+            if indent != 4 || !text.starts_with(' ') {
+                error((number, 1), "entries must be written as `    - text` (four spaces, dash, space)");
+            }
+            if text.trim().is_empty() {
+                error((number, indent + 1), "empty entry");
+            }
+            return;
+        }
+
+        if let Some(name) = trimmed.strip_suffix(':').filter(|_| indent < 4 || !has_entry) {
+            let name = name.trim().to_string();
+            if section.categories.iter().any(|c| c.name == name) {
+                error((number, indent + 1), &format!("category `{}` appears twice in this section", name));
+            }
+            section.categories.push(Category { name, entries: Vec::new() });
+            if indent != 2 {
+                error((number, 1), "categories must be indented by exactly two spaces");
+            }
+            return;
+        }
+
+        match section.categories.last_mut().and_then(|c| c.entries.last_mut()) {
+            Some(entry) if indent >= 4 => {
+                entry.push('\n');
+                entry.push_str(trimmed);
+                if indent != 6 {
+                    error((number, 1), "continuation lines of an entry must be indented by exactly six spaces");
+                }
+            }
+            _ => {
+                self.changelog.lossy = true;
+                error((number, indent + 1), "unrecognized line; expected a category (`  Name:`) or an entry (`    - text`)");
+            }
+        }
+    }
+
+    fn finish(mut self) -> (Changelog, Vec<Diagnostic>) {
+        let mut seen: Vec<(&str, usize)> = Vec::new();
+        let mut duplicates = Vec::new();
+        for section in &self.changelog.sections {
+            if section.version.is_empty() {
+                duplicates.push(((section.line, 1), "section has no `Version:` line".to_string()));
+                continue;
+            }
+            match seen.iter().find(|(v, _)| *v == section.version) {
+                Some((_, first)) => duplicates.push(((section.line, 1), format!("version {} is already listed at line {}", section.version, first))),
+                None => seen.push((&section.version, section.line)),
+            }
+        }
+        for (position, message) in duplicates {
+            self.error(position, message);
+        }
+        self.diagnostics.sort_by_key(|d| (d.line, d.column));
+        (self.changelog, self.diagnostics)
+    }
+}
+
+/// Diagnostics for `changelog.txt` in `mod_root`, if there is one; its top section must match `info_version`
+pub fn validate_changelog(mod_root: &Path, info_version: Option<&str>) -> Result<Vec<Diagnostic>> {
+    let path = mod_root.join(CHANGELOG_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let Ok(source) = String::from_utf8(fs::read(&path)?) else {
+        return Ok(vec![Diagnostic::error(&path, (1, 1), "changelog.txt must be valid UTF-8")]);
+    };
+
+    let (changelog, mut diagnostics) = Changelog::parse(&source, &path);
+    if let (Some(top), Some(info_version)) = (changelog.sections.first(), info_version)
+        && !top.version.is_empty()
+        && top.version != info_version
+    {
+        diagnostics.push(Diagnostic::error(
+            &path,
+            (top.line, 1),
+            format!("newest changelog section is for {} but info.json has version {}", top.version, info_version),
+        ));
+        diagnostics.sort_by_key(|d| (d.line, d.column));
+    }
+    Ok(diagnostics)
+}
+
+/// Load and parse a mod's changelog, failing on anything `to_text` would not reproduce
+pub fn load_changelog(mod_root: &Path) -> Result<Changelog> {
+    let path = mod_root.join(CHANGELOG_FILE);
+    if !path.exists() {
+        bail!("{} not found", path.display());
+    }
+    let source = fs::read_to_string(&path)?;
+    let (changelog, diagnostics) = Changelog::parse(&source, &path);
+    if changelog.lossy {
+        let errors: Vec<String> = diagnostics.iter().filter(|d| d.is_error()).map(|d| d.to_string()).collect();
+        bail!(
+            "{} has lines that don't belong to any section or category; fix them first:\n{}",
+            path.display(),
+            errors.join("\n")
+        );
+    }
+    Ok(changelog)
+}
+
+/// Print the normalized changelog.txt of each selected mod
+pub fn format_changelogs(mod_path: Option<PathBuf>, settings: &Settings) -> Result<()> {
+    let mods = select_mods(mod_path, settings)?;
+    for m in &mods {
+        let changelog = load_changelog(&m.root)?;
+        if mods.len() > 1 {
+            println!("==> {} <==", m.root.join(CHANGELOG_FILE).display());
+        }
+        print!("{}", changelog.to_text());
+    }
+    Ok(())
+}
+
+/// Print one version's changelog entries for each selected mod, by default the version in info.json
+pub fn show_changelogs(version: Option<String>, mod_path: Option<PathBuf>, settings: &Settings, format: NotesFormat) -> Result<()> {
+    let mods = select_mods(mod_path, settings)?;
+    for m in &mods {
+        let changelog = load_changelog(&m.root)?;
+        let version = version.as_deref().unwrap_or(&m.info.version);
+        let section = changelog
+            .section(version)
+            .with_context(|| format!("{} has no section for version {}", m.root.join(CHANGELOG_FILE).display(), version))?;
+
+        if mods.len() > 1 {
+            match format {
+                NotesFormat::Markdown => println!("## {} {}\n", m.info.name, version),
+                NotesFormat::Text => println!("{} {}", m.info.name, version),
+            }
+        }
+        print!("{}", section.render(format));
+        if mods.len() > 1 {
+            println!();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> (Changelog, Vec<Diagnostic>) {
+        Changelog::parse(source, Path::new("changelog.txt"))
+    }
+
+    #[test]
+    fn well_formed_text_round_trips() {
+        let source = format!(
+            "{sep}\nVersion: 1.1.0\nDate: 2024-05-01\n  Features:\n    - Added planets.\n    - A longer entry\n      over two lines.\n  Bugfixes:\n    - Fixed a crash.\n{sep}\nVersion: 1.0.0\n  Changes:\n    - First release.\n",
+            sep = CHANGELOG_SEPARATOR
+        );
+        let (changelog, diagnostics) = parse(&source);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>());
+        assert_eq!(changelog.sections.len(), 2);
+        assert_eq!(changelog.sections[0].categories[0].entries[1], "A longer entry\nover two lines.");
+        assert_eq!(changelog.to_text(), source);
+    }
+
+    #[test]
+    fn misindented_text_is_normalized() {
+        let source = format!("{}\nVersion: 1.0.0\n Changes:\n   - Tabs\tand spaces.\n", CHANGELOG_SEPARATOR);
+        let (changelog, diagnostics) = parse(&source);
+        assert!(!changelog.lossy);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(changelog.to_text(), format!("{}\nVersion: 1.0.0\n  Changes:\n    - Tabs    and spaces.\n", CHANGELOG_SEPARATOR));
+    }
+
+    #[test]
+    fn lines_outside_a_section_are_lossy() {
+        let (changelog, diagnostics) = parse("  Changes:\n    - orphan\n");
+        assert!(changelog.lossy);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+    }
+
+    #[test]
+    fn sections_render_as_release_notes() {
+        let source = format!(
+            "{sep}\nVersion: 1.1.0\n  Features:\n    - Added planets.\n    - A longer entry\n      over two lines.\n  Bugfixes:\n    - Fixed a crash.\n",
+            sep = CHANGELOG_SEPARATOR
+        );
+        let (changelog, _) = parse(&source);
+        let section = changelog.section("1.1.0").unwrap();
+        assert!(changelog.section("1.0.0").is_none());
+
+        assert_eq!(
+            section.render(NotesFormat::Markdown),
+            "### Features\n\n- Added planets.\n- A longer entry\n  over two lines.\n\n### Bugfixes\n\n- Fixed a crash.\n"
+        );
+        assert_eq!(
+            section.render(NotesFormat::Text),
+            "Features:\n  - Added planets.\n  - A longer entry\n    over two lines.\nBugfixes:\n  - Fixed a crash.\n"
+        );
+    }
+}
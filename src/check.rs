@@ -0,0 +1,52 @@
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+
+use crate::changelog::validate_changelog;
+use crate::config::Settings;
+use crate::diagnostics::Diagnostic;
+use crate::locale::validate_locales;
+use crate::locale_refs::validate_locale_references;
+use crate::mod_info::{resolve_mod_paths, validate_info, Info};
+
+/// Validate every selected mod and report all problems before anything is built
+pub fn check_mods(mod_path: Option<PathBuf>, settings: &Settings) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let mods = resolve_mod_paths(mod_path, &cwd, &settings.discovery)?;
+
+    if mods.is_empty() {
+        bail!("No mods found. Place an info.json in the repo root or in subfolders.");
+    }
+
+    let mut errors = 0;
+    for mod_root in &mods {
+        let diagnostics = check_one(mod_root, settings)?;
+        errors += diagnostics.iter().filter(|d| d.is_error()).count();
+
+        if diagnostics.is_empty() {
+            println!("✅ {} looks good", mod_root.display());
+        }
+        for diagnostic in diagnostics {
+            println!("{}", diagnostic);
+        }
+    }
+
+    if errors > 0 {
+        bail!("Found {} error(s)", errors);
+    }
+    Ok(())
+}
+
+/// Collect every diagnostic for a single mod
+fn check_one(mod_root: &Path, settings: &Settings) -> Result<Vec<Diagnostic>> {
+    let mut diagnostics = validate_info(mod_root)?;
+    let info = Info::load_from_dir(mod_root).ok();
+    diagnostics.extend(validate_changelog(mod_root, info.as_ref().map(|i| i.version.as_str()))?);
+    diagnostics.extend(validate_locales(mod_root)?);
+
+    let name = match &info {
+        Some(info) => info.name.clone(),
+        None => mod_root.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+    };
+    diagnostics.extend(validate_locale_references(mod_root, &name, &settings.build_config(&name))?);
+    Ok(diagnostics)
+}
@@ -0,0 +1,148 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+use crate::mod_info::{DependencyKind, Info, Version};
+use crate::mod_list::ModList;
+
+/// The mod every game ships with
+pub const BASE_MOD: &str = "base";
+
+/// Space Age expansion mods, shipped in `data/` of 2.0 games that own the DLC and otherwise unavailable
+pub const DLC_MODS: &[&str] = &["space-age", "quality", "elevated-rails"];
+
+/// Reasons the game at `game` would refuse to load the mod described by `info`
+pub fn game_compatibility(info: &Info, game: Version) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+
+    let (major, minor) = info.factorio_series()?;
+    // 1.0 was a re-release of 0.18 and still loads mods made for it
+    let accepted = (major, minor) == (game.major, game.minor) || ((major, minor) == (0, 18) && (game.major, game.minor) == (1, 0));
+    if !accepted {
+        problems.push(format!("`{}` is made for Factorio {}.{} but the game is {}", info.name, major, minor, game));
+    }
+
+    for dep in info.parsed_dependencies()? {
+        if dep.name != BASE_MOD || dep.kind == DependencyKind::Incompatible {
+            continue;
+        }
+        if let Some((op, wanted)) = dep.constraint
+            && !op.matches(game, wanted)
+        {
+            problems.push(format!("`{}` depends on `{}` but the game is {}", info.name, dep, game));
+        }
+    }
+    Ok(problems)
+}
+
+/// What a mods directory provides: the newest version of each installed mod and its enabled state
+pub struct InstalledSet<'a> {
+    pub versions: &'a BTreeMap<String, Version>,
+    pub mod_list: Option<&'a ModList>,
+    /// Mods shipped with the game, see `builtin_mods`
+    pub builtin: &'a [&'static str],
+    /// Version of the game, which is also the version of every built-in mod
+    pub game: Option<Version>,
+}
+
+impl InstalledSet<'_> {
+    /// Version of `name` if the game would load it: installed (or built in) and not disabled.
+    /// The outer `None` means missing; `Some(None)` means present with an unknown version.
+    fn active(&self, name: &str) -> Option<Option<Version>> {
+        let enabled = self.mod_list.and_then(|list| list.get(name)).is_none_or(|entry| entry.enabled);
+        if !enabled {
+            return None;
+        }
+        if self.builtin.contains(&name) {
+            return Some(self.game);
+        }
+        self.versions.get(name).map(|v| Some(*v))
+    }
+
+    fn is_disabled(&self, name: &str) -> bool {
+        self.mod_list.and_then(|list| list.get(name)).is_some_and(|entry| !entry.enabled)
+    }
+}
+
+/// Dependencies of `info` the game would trip over with what `installed` provides
+pub fn dependency_problems(info: &Info, installed: &InstalledSet) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+
+    for dep in info.parsed_dependencies()? {
+        let active = installed.active(&dep.name);
+        match (dep.kind, active) {
+            (DependencyKind::Incompatible, Some(_)) => {
+                problems.push(format!("`{}` is incompatible with `{}`, which is installed and enabled", info.name, dep.name));
+            }
+            (DependencyKind::Incompatible, None) => {}
+            (DependencyKind::Required | DependencyKind::NoLoadOrder, None) => {
+                let state = if installed.is_disabled(&dep.name) { "disabled" } else { "not installed" };
+                problems.push(format!("`{}` requires `{}`, which is {}", info.name, dep, state));
+            }
+            (DependencyKind::Optional | DependencyKind::HiddenOptional, None) => {}
+            (_, Some(version)) => {
+                if let (Some((op, wanted)), Some(version)) = (dep.constraint, version)
+                    && !op.matches(version, wanted)
+                {
+                    problems.push(format!("`{}` depends on `{}` but {} is installed", info.name, dep, version));
+                }
+            }
+        }
+    }
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(factorio_version: &str, dependencies: &[&str]) -> Info {
+        serde_json::from_value(serde_json::json!({
+            "name": "m",
+            "version": "1.0.0",
+            "factorio_version": factorio_version,
+            "dependencies": dependencies,
+        }))
+        .unwrap()
+    }
+
+    fn game(version: &str) -> Version {
+        version.parse().unwrap()
+    }
+
+    #[test]
+    fn factorio_version_must_match_the_game_series() {
+        assert!(game_compatibility(&info("2.0", &["base"]), game("2.0.28")).unwrap().is_empty());
+        assert!(game_compatibility(&info("0.18", &["base"]), game("1.0.0")).unwrap().is_empty());
+
+        let problems = game_compatibility(&info("1.1", &["base"]), game("2.0.28")).unwrap();
+        assert_eq!(problems, ["`m` is made for Factorio 1.1 but the game is 2.0.28"]);
+    }
+
+    #[test]
+    fn base_constraint_is_checked_against_the_game() {
+        assert!(game_compatibility(&info("2.0", &["base >= 2.0.20"]), game("2.0.28")).unwrap().is_empty());
+        assert!(game_compatibility(&info("2.0", &["! base"]), game("2.0.28")).unwrap().is_empty());
+
+        let problems = game_compatibility(&info("2.0", &["base >= 2.0.30"]), game("2.0.28")).unwrap();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("but the game is 2.0.28"), "{:?}", problems);
+    }
+
+    #[test]
+    fn dependencies_are_resolved_against_the_mods_folder() {
+        let versions: BTreeMap<String, Version> =
+            [("lib", "1.2.0"), ("old", "0.5.0"), ("rival", "1.0.0")].into_iter().map(|(name, v)| (name.to_string(), game(v))).collect();
+        let installed = InstalledSet { versions: &versions, mod_list: None, builtin: &[BASE_MOD, "space-age"], game: Some(game("2.0.28")) };
+        let deps = ["base >= 2.0", "space-age", "lib >= 1.0", "old >= 1.0.0", "missing", "? optional", "! rival", "! absent"];
+
+        let problems = dependency_problems(&info("2.0", &deps), &installed).unwrap();
+        assert_eq!(
+            problems,
+            [
+                "`m` depends on `old >= 1.0.0` but 0.5.0 is installed",
+                "`m` requires `missing`, which is not installed",
+                "`m` is incompatible with `rival`, which is installed and enabled",
+            ]
+        );
+    }
+}
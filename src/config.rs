@@ -1,38 +1,370 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::locate::{find_installations, game_version, Installation};
+use crate::mod_info::Version;
+use crate::platform::{factorio_mods_dir, mods_dir_for_install, InstallTarget, MODS_DIR_ENV};
+use crate::portal::{API_KEY_ENV, DEFAULT_PORTAL_URL, PORTAL_URL_ENV};
+
+/// Patterns excluded from every zip, on top of any `.factorioignore` rules, unless `default_excludes` replaces them
+const DEFAULT_EXCLUDES: &[&str] = &["/build", "/.git", "/.github", "/.idea", "/.vscode", ".factorioignore"];
+
+/// Output directory used when neither the CLI nor a config file sets one
+const DEFAULT_OUT_DIR: &str = "build";
+
+/// Thumbnail, relative to the workspace root, packed into mods without one unless `default_thumbnail` is set
+const DEFAULT_THUMBNAIL: &str = "assets/default_thumbnail.png";
+
+/// Dedicated config file, looked up in the repo root before Cargo.toml metadata
+pub const CONFIG_FILE: &str = "factorio.toml";
+
+/// How git state narrows down the files packed into a zip
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitFilter {
+    /// Pack every file on disk that isn't excluded
+    Off,
+    /// Skip anything git would ignore (`.gitignore`, `.git/info/exclude`, global excludes)
+    Ignored,
+    /// Only pack files tracked by git
+    TrackedOnly,
+}
+
+/// Build options from a single source (CLI flags, a config section); `None` means "not set here".
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuildOptions {
+    pub out_dir: Option<PathBuf>,
+    /// Thumbnail for mods without one; an empty path turns the fallback off.
+    pub default_thumbnail: Option<PathBuf>,
+    /// Replaces the built-in exclude list (`/build`, `/.git`, ...) instead of adding to it.
+    pub default_excludes: Option<Vec<String>>,
+    /// Extra gitignore-style exclude patterns; these add up across sources instead of overriding.
+    pub excludes: Vec<String>,
+    #[serde(rename = "git")]
+    pub git_filter: Option<GitFilter>,
+    pub reproducible: Option<bool>,
+}
+
+impl BuildOptions {
+    /// Layer `self` on top of `fallback`: values set here win, excludes accumulate
+    pub fn or(&self, fallback: &BuildOptions) -> BuildOptions {
+        BuildOptions {
+            out_dir: self.out_dir.clone().or_else(|| fallback.out_dir.clone()),
+            default_thumbnail: self.default_thumbnail.clone().or_else(|| fallback.default_thumbnail.clone()),
+            default_excludes: self.default_excludes.clone().or_else(|| fallback.default_excludes.clone()),
+            excludes: fallback.excludes.iter().chain(&self.excludes).cloned().collect(),
+            git_filter: self.git_filter.or(fallback.git_filter),
+            reproducible: self.reproducible.or(fallback.reproducible),
+        }
+    }
+}
+
 /// Configuration for building mods
 pub struct BuildConfig {
     pub verbose: bool,
+    pub out_dir: PathBuf,
     pub default_thumbnail: Option<Vec<u8>>,
-    pub excludes: &'static [&'static str],
+    pub git_filter: GitFilter,
+    /// Produce byte-identical zips: sorted entries, fixed timestamps, permissions and compression.
+    pub reproducible: bool,
+    /// Gitignore-style patterns, relative to the mod root, that are never packed.
+    pub excludes: Vec<String>,
 }
 
 impl BuildConfig {
-    pub fn new(verbose: bool, default_thumbnail: Option<PathBuf>) -> Self {
+    /// Fill in built-in defaults for everything `options` leaves unset; `root` is the workspace root
+    pub fn new(verbose: bool, options: BuildOptions, root: &Path) -> Self {
+        let default_excludes = options.default_excludes.unwrap_or_else(|| DEFAULT_EXCLUDES.iter().map(|p| p.to_string()).collect());
         Self {
             verbose,
-            default_thumbnail: load_default_thumbnail_bytes(&default_thumbnail),
-            excludes: &["build", ".git", ".github", ".idea", ".vscode"],
+            out_dir: options.out_dir.unwrap_or_else(|| PathBuf::from(DEFAULT_OUT_DIR)),
+            default_thumbnail: load_default_thumbnail_bytes(options.default_thumbnail.as_deref(), root),
+            git_filter: options.git_filter.unwrap_or(GitFilter::Ignored),
+            reproducible: options.reproducible.unwrap_or(false),
+            excludes: default_excludes.into_iter().chain(options.excludes).collect(),
         }
     }
 
     pub fn log(&self, message: &str) {
         if self.verbose {
-            println!("{}", message);
+            eprintln!("{}", message);
+        }
+    }
+}
+
+/// Where to look for mods when no explicit mod path is given
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscoveryConfig {
+    /// Directory levels below the repo root to search (0 = only the root itself)
+    pub max_depth: usize,
+    /// Globs (relative to the repo root) a mod folder must match; empty means all
+    pub include: Vec<String>,
+    /// Globs (relative to the repo root) of mod folders to skip
+    pub exclude: Vec<String>,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self { max_depth: 3, include: Vec::new(), exclude: Vec::new() }
+    }
+}
+
+/// A named deploy target from `[targets.<name>]`: either a mods directory or a game install
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetConfig {
+    pub mods_dir: Option<PathBuf>,
+    /// Game folder; its mods directory is found through config-path.cfg and config.ini
+    pub install_dir: Option<PathBuf>,
+}
+
+/// Where the mod portal lives, for `deps fetch` and `publish`
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PortalConfig {
+    pub url: Option<String>,
+    /// API key with the "ModPortal: Upload Mods" (and "Edit Mods" for `--readme`) permissions
+    pub api_key: Option<String>,
+}
+
+/// Which mods directories a command should act on, as requested on the command line
+#[derive(Default)]
+pub struct TargetSelection {
+    pub mods_dir: Option<PathBuf>,
+    pub names: Vec<String>,
+}
+
+/// Workspace-wide settings from `factorio.toml` or `[package.metadata.factorio]`.
+///
+/// ```toml
+/// [build]            # defaults for every mod
+/// out_dir = "dist"
+/// default_thumbnail = "art/thumbnail.png"   # relative to this file
+/// default_excludes = ["/build", "/.git"]    # replaces the built-in list
+/// excludes = ["docs/", "*.xcf"]
+///
+/// [discovery]
+/// max_depth = 2
+///
+/// [mods.planets]     # overrides for the mod named `planets` in its info.json
+/// default_thumbnail = "assets/planets.png"
+///
+/// [targets.server]   # selected with `--target server`
+/// mods_dir = "/opt/factorio/mods"
+/// ```
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    pub build: BuildOptions,
+    pub discovery: DiscoveryConfig,
+    pub mods: BTreeMap<String, BuildOptions>,
+    pub targets: BTreeMap<String, TargetConfig>,
+    pub portal: PortalConfig,
+    /// Directory the config was loaded from; relative paths in it are resolved against this
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+impl WorkspaceConfig {
+    /// Load `factorio.toml` from `root`, else Cargo.toml's `package`/`workspace` metadata, else defaults
+    pub fn load(root: &Path) -> Result<Self> {
+        let mut config = Self::load_file(root)?;
+        config.root = root.to_path_buf();
+        for options in std::iter::once(&mut config.build).chain(config.mods.values_mut()) {
+            if let Some(thumbnail) = &mut options.default_thumbnail
+                && !thumbnail.as_os_str().is_empty()
+            {
+                *thumbnail = root.join(&*thumbnail);
+            }
+        }
+        Ok(config)
+    }
+
+    fn load_file(root: &Path) -> Result<Self> {
+        let dedicated = root.join(CONFIG_FILE);
+        if dedicated.exists() {
+            let content = fs::read_to_string(&dedicated)?;
+            return toml::from_str(&content).with_context(|| format!("Invalid {}", dedicated.display()));
+        }
+
+        let manifest = root.join("Cargo.toml");
+        if !manifest.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&manifest)?;
+        let table: toml::Table = toml::from_str(&content).with_context(|| format!("Invalid {}", manifest.display()))?;
+        let metadata = ["package", "workspace"]
+            .iter()
+            .find_map(|section| table.get(*section)?.get("metadata")?.get("factorio"));
+
+        match metadata {
+            Some(value) => value
+                .clone()
+                .try_into()
+                .with_context(|| format!("Invalid [metadata.factorio] in {}", manifest.display())),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Resolve a `[targets.<name>]` entry to a mods directory; `None` when there is no such entry
+    fn target(&self, name: &str) -> Option<Result<InstallTarget>> {
+        let target = self.targets.get(name)?;
+        Some(self.resolve_target(name, target))
+    }
+
+    fn resolve_target(&self, name: &str, target: &TargetConfig) -> Result<InstallTarget> {
+        let mods_dir = match (&target.mods_dir, &target.install_dir) {
+            (Some(mods_dir), _) => mods_dir.clone(),
+            (None, Some(install_dir)) => mods_dir_for_install(install_dir)?,
+            (None, None) => bail!("Target `{}` needs `mods_dir` or `install_dir`", name),
+        };
+        let game_version = target.install_dir.as_deref().and_then(game_version);
+        Ok(InstallTarget { name: name.to_string(), mods_dir, game_version, game_dir: target.install_dir.clone() })
+    }
+
+    /// Options for one mod: its `[mods.<name>]` section layered over `[build]`
+    pub fn options_for(&self, mod_name: &str) -> BuildOptions {
+        match self.mods.get(mod_name) {
+            Some(overrides) => overrides.or(&self.build),
+            None => self.build.clone(),
         }
     }
 }
 
-/// Load thumbnail bytes from explicit path or default location
-fn load_default_thumbnail_bytes(explicit: &Option<PathBuf>) -> Option<Vec<u8>> {
-    if let Some(path) = explicit {
-        if let Ok(bytes) = fs::read(path) {
-            return Some(bytes);
+/// Everything a command needs to find and build mods: the config file merged with CLI flags.
+///
+/// Precedence, highest first: CLI flags, `[mods.<name>]`, `[build]`, built-in defaults.
+pub struct Settings {
+    pub verbose: bool,
+    pub discovery: DiscoveryConfig,
+    cli: BuildOptions,
+    selection: TargetSelection,
+    workspace: WorkspaceConfig,
+}
+
+impl Settings {
+    pub fn new(verbose: bool, discovery: DiscoveryConfig, cli: BuildOptions, selection: TargetSelection, workspace: WorkspaceConfig) -> Self {
+        Self { verbose, discovery, cli, selection, workspace }
+    }
+
+    /// Mods directories to act on: every `--target` plus `--mods-dir`; without either,
+    /// `$FACTORIO_MODS_DIR`, then a `[targets.default]` entry, then the platform default.
+    pub fn targets(&self) -> Result<Vec<InstallTarget>> {
+        let mut targets = self.selection.names.iter().map(|name| self.named_target(name)).collect::<Result<Vec<_>>>()?;
+        if let Some(mods_dir) = &self.selection.mods_dir {
+            targets.push(InstallTarget { name: "--mods-dir".to_string(), mods_dir: mods_dir.clone(), game_version: None, game_dir: None });
+        }
+
+        if targets.is_empty() {
+            targets.push(if let Some(mods_dir) = std::env::var_os(MODS_DIR_ENV) {
+                InstallTarget { name: MODS_DIR_ENV.to_string(), mods_dir: PathBuf::from(mods_dir), game_version: None, game_dir: None }
+            } else if let Some(target) = self.workspace.target("default") {
+                target?
+            } else {
+                InstallTarget { name: "default".to_string(), mods_dir: factorio_mods_dir()?, game_version: None, game_dir: None }
+            });
+        }
+
+        if targets.iter().any(|t| t.game_version.is_none()) {
+            let installations = find_installations();
+            for target in targets.iter_mut().filter(|t| t.game_version.is_none()) {
+                if let Some((dir, version)) = guess_game(&target.mods_dir, &installations) {
+                    target.game_dir = Some(dir);
+                    target.game_version = Some(version);
+                }
+            }
+        }
+        Ok(targets)
+    }
+
+    /// A `--target` value: a `[targets]` entry, else a detected installation by name or version prefix
+    fn named_target(&self, name: &str) -> Result<InstallTarget> {
+        if let Some(target) = self.workspace.target(name) {
+            return target;
+        }
+
+        let installations = find_installations();
+        let matching: Vec<&Installation> = match installations.iter().find(|i| i.name == name) {
+            Some(exact) => vec![exact],
+            None => installations.iter().filter(|i| i.matches(name)).collect(),
+        };
+        match matching.as_slice() {
+            [install] => Ok(InstallTarget {
+                name: install.name.clone(),
+                mods_dir: install.mods_dir.clone(),
+                game_version: install.version,
+                game_dir: Some(install.dir.clone()),
+            }),
+            [] => {
+                let known: Vec<&str> = self.workspace.targets.keys().chain(installations.iter().map(|i| &i.name)).map(String::as_str).collect();
+                bail!("Unknown target `{}` (known: {}; see `cargo factorio locate`)", name, if known.is_empty() { "none".to_string() } else { known.join(", ") })
+            }
+            several => {
+                let names: Vec<&str> = several.iter().map(|i| i.name.as_str()).collect();
+                bail!("Target `{}` matches several installations ({}); pick one by name", name, names.join(", "))
+            }
         }
     }
-    
-    // Try default location
-    let fallback = Path::new("assets").join("default_thumbnail.png");
-    fs::read(fallback).ok()
-}
\ No newline at end of file
+
+    /// The single mods directory for commands that can only act on one
+    pub fn single_target(&self) -> Result<InstallTarget> {
+        let mut targets = self.targets()?;
+        if targets.len() > 1 {
+            bail!("This command works on one mods directory at a time; pass a single --target or --mods-dir");
+        }
+        Ok(targets.remove(0))
+    }
+
+    /// Mod portal base URL: `cli`, then `$FACTORIO_PORTAL_URL`, then `[portal] url`, then the official portal
+    pub fn portal_url(&self, cli: Option<String>) -> String {
+        cli.or_else(|| std::env::var(PORTAL_URL_ENV).ok())
+            .or_else(|| self.workspace.portal.url.clone())
+            .unwrap_or_else(|| DEFAULT_PORTAL_URL.to_string())
+    }
+
+    /// Mod portal API key: `$FACTORIO_API_KEY`, then `[portal] api_key`
+    pub fn portal_api_key(&self) -> Result<String> {
+        std::env::var(API_KEY_ENV)
+            .ok()
+            .or_else(|| self.workspace.portal.api_key.clone())
+            .with_context(|| format!("No mod portal API key: set {} or `api_key` under [portal]", API_KEY_ENV))
+    }
+
+    /// Resolved build configuration for the mod named `mod_name`
+    pub fn build_config(&self, mod_name: &str) -> BuildConfig {
+        BuildConfig::new(self.verbose, self.cli.or(&self.workspace.options_for(mod_name)), &self.workspace.root)
+    }
+
+    pub fn log(&self, message: &str) {
+        if self.verbose {
+            eprintln!("{}", message);
+        }
+    }
+}
+
+/// Game folder and version for a bare mods directory: a detected installation using it, else a game folder
+/// directly above it (portable and headless installs keep `mods/` next to `data/`)
+fn guess_game(mods_dir: &Path, installations: &[Installation]) -> Option<(PathBuf, Version)> {
+    installations
+        .iter()
+        .find(|i| i.mods_dir == mods_dir)
+        .and_then(|i| Some((i.dir.clone(), i.version?)))
+        .or_else(|| {
+            let dir = mods_dir.parent()?;
+            Some((dir.to_path_buf(), game_version(dir)?))
+        })
+}
+
+/// Load thumbnail bytes from the configured path, else from `assets/default_thumbnail.png` in the workspace root
+fn load_default_thumbnail_bytes(configured: Option<&Path>, root: &Path) -> Option<Vec<u8>> {
+    match configured {
+        Some(path) if path.as_os_str().is_empty() => None,
+        Some(path) => fs::read(path).ok(),
+        None => fs::read(root.join(DEFAULT_THUMBNAIL)).ok(),
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::compat::DLC_MODS;
+use crate::config::Settings;
+use crate::installed::installed_versions;
+use crate::installer::{replace_stale_versions, ReplacePolicy};
+use crate::locate::builtin_mods;
+use crate::mod_info::Dependency;
+use crate::portal::{Credentials, Portal};
+use crate::workspace::select_mods;
+
+/// Download the required dependencies of the selected mods that each target is missing, recursively
+pub fn fetch_dependencies(mod_path: Option<PathBuf>, settings: &Settings, portal_url: Option<String>) -> Result<()> {
+    let targets = settings.targets()?;
+    let mods = select_mods(mod_path, settings)?;
+    let portal = Portal::new(&settings.portal_url(portal_url));
+    settings.log(&format!("🌐 Mod portal: {}", portal.base_url()));
+
+    let ours: HashSet<&str> = mods.iter().map(|m| m.info.name.as_str()).collect();
+    for target in &targets {
+        fs::create_dir_all(&target.mods_dir)?;
+        let mut versions = installed_versions(&target.mods_dir)?;
+        let builtin = builtin_mods(target.game_dir.as_deref(), target.game_version);
+        let mut credentials = None;
+        let mut fetched = 0;
+
+        let mut queue = VecDeque::new();
+        for m in &mods {
+            let series = match target.game_version {
+                Some(game) => (game.major, game.minor),
+                None => m.info.factorio_series()?,
+            };
+            for dep in m.info.parsed_dependencies()?.into_iter().filter(Dependency::is_required) {
+                queue.push_back((m.info.name.clone(), dep, series));
+            }
+        }
+
+        while let Some((requirer, dep, series)) = queue.pop_front() {
+            if builtin.contains(&dep.name.as_str()) || ours.contains(dep.name.as_str()) {
+                continue;
+            }
+            if DLC_MODS.contains(&dep.name.as_str()) {
+                println!("⚠️  `{}` requires `{}`, which comes with the Space Age expansion and can't be downloaded (target {})", requirer, dep.name, target.name);
+                continue;
+            }
+            let satisfied = versions
+                .get(&dep.name)
+                .is_some_and(|installed| dep.constraint.is_none_or(|(op, wanted)| op.matches(*installed, wanted)));
+            if satisfied {
+                continue;
+            }
+
+            let listing = portal.mod_full(&dep.name)?;
+            let release = listing.best_release(&dep, series).with_context(|| {
+                format!("No release of `{}` for Factorio {}.{} satisfies `{}` (required by {})", listing.name, series.0, series.1, dep, requirer)
+            })?;
+            let version = release.parsed_version().with_context(|| format!("Invalid version {:?} on the portal", release.version))?;
+
+            if credentials.is_none() {
+                credentials = Some(Credentials::load(&target.mods_dir)?);
+            }
+            let dest = target.mods_dir.join(&release.file_name);
+            portal.download(release, credentials.as_ref().unwrap(), &dest)?;
+            replace_stale_versions(&target.mods_dir, &listing.name, &dest, ReplacePolicy::Remove, None, false)?;
+            println!("⬇️  Fetched {} {} → {} (required by {})", listing.name, version, dest.display(), requirer);
+            versions.insert(listing.name.clone(), version);
+            fetched += 1;
+
+            for entry in &release.info_json.dependencies {
+                match entry.parse::<Dependency>() {
+                    Ok(next) if next.is_required() => queue.push_back((listing.name.clone(), next, series)),
+                    Ok(_) => {}
+                    Err(e) => println!("⚠️  Skipping dependency {:?} of {}: {}", entry, listing.name, e),
+                }
+            }
+        }
+
+        if fetched == 0 {
+            println!("ℹ️  All required dependencies are present in {}", target.mods_dir.display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// How serious a reported problem is
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in one of a mod's files, with a 1-based line/column position
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(file: &Path, (line, column): (usize, usize), message: impl Into<String>) -> Self {
+        Self { severity: Severity::Error, file: file.to_path_buf(), line, column, message: message.into() }
+    }
+
+    pub fn warning(file: &Path, (line, column): (usize, usize), message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, file: file.to_path_buf(), line, column, message: message.into() }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "❌ error",
+            Severity::Warning => "⚠️  warning",
+        };
+        write!(f, "{}: {}:{}:{}: {}", label, self.file.display(), self.line, self.column, self.message)
+    }
+}
+
+/// 1-based line and column of a byte offset in `source`
+pub fn position_of(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
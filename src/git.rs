@@ -0,0 +1,47 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Run a git command inside `dir` and return its stdout
+fn git_output(dir: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git; is it installed and on PATH?")?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed in {}: {}",
+            args.join(" "),
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Absolute paths of every file tracked by git under `dir`
+pub fn tracked_files(dir: &Path) -> Result<HashSet<PathBuf>> {
+    let stdout = git_output(dir, &["ls-files", "-z", "--cached"])
+        .with_context(|| format!("{} is not inside a git repository", dir.display()))?;
+
+    Ok(stdout
+        .split(|b| *b == 0)
+        .filter(|rel| !rel.is_empty())
+        .map(|rel| dir.join(String::from_utf8_lossy(rel).as_ref()))
+        .collect())
+}
+
+/// Unix timestamp of the last commit touching `dir`
+pub fn last_commit_time(dir: &Path) -> Result<i64> {
+    let stdout = git_output(dir, &["log", "-1", "--format=%ct", "--", "."])?;
+    let stdout = String::from_utf8_lossy(&stdout);
+    let stamp = stdout.trim();
+    if stamp.is_empty() {
+        bail!("No commits touch {}", dir.display());
+    }
+    stamp.parse().with_context(|| format!("Unexpected git timestamp {:?}", stamp))
+}
@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::mod_info::{Info, Version};
+
+/// Marker left in a folder copied by `link`, so it is known to be safe to delete
+pub const COPY_MARKER: &str = ".cargo-factorio-link";
+
+/// What shape an installed copy of a mod has in the mods directory
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    Zip,
+    Folder,
+    Symlink,
+}
+
+/// One zip, folder or symlink in a mods directory that belongs to a mod
+pub struct InstalledArtifact {
+    pub path: PathBuf,
+    pub kind: ArtifactKind,
+}
+
+impl InstalledArtifact {
+    /// Whether deleting the artifact would delete `root`, e.g. a mod developed right inside the mods folder.
+    /// Symlinks never do: only the link itself is removed.
+    pub fn holds(&self, root: &Path) -> bool {
+        if self.kind == ArtifactKind::Symlink {
+            return false;
+        }
+        match (fs::canonicalize(&self.path), fs::canonicalize(root)) {
+            (Ok(artifact), Ok(root)) => root.starts_with(artifact),
+            _ => false,
+        }
+    }
+
+    /// A folder `link` copied the mod into because it could not create a symlink
+    pub fn is_dev_copy(&self) -> bool {
+        self.kind == ArtifactKind::Folder && self.path.join(COPY_MARKER).exists()
+    }
+
+    /// A symlink to `source` or a folder copied by `link`, as opposed to something the user put there
+    pub fn is_link_of(&self, source: Option<&Path>) -> bool {
+        match self.kind {
+            ArtifactKind::Symlink => source.is_some_and(|source| {
+                matches!((fs::canonicalize(&self.path), fs::canonicalize(source)), (Ok(target), Ok(source)) if target == source)
+            }),
+            ArtifactKind::Folder => self.is_dev_copy(),
+            ArtifactKind::Zip => false,
+        }
+    }
+}
+
+/// Every artifact of the mod `name` in `mods_dir`: `name_<version>.zip`, `name_<version>/` and `name/`
+pub fn find_installed(mods_dir: &Path, name: &str) -> Result<Vec<InstalledArtifact>> {
+    let mut found = Vec::new();
+    if !mods_dir.exists() {
+        return Ok(found);
+    }
+
+    for entry in fs::read_dir(mods_dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let file_type = entry.file_type()?;
+
+        let (stem, kind) = if file_type.is_symlink() {
+            (file_name.as_str(), ArtifactKind::Symlink)
+        } else if file_type.is_dir() {
+            (file_name.as_str(), ArtifactKind::Folder)
+        } else if let Some(stem) = file_name.strip_suffix(".zip") {
+            (stem, ArtifactKind::Zip)
+        } else {
+            continue;
+        };
+
+        let bare = stem == name && kind != ArtifactKind::Zip;
+        let versioned = stem
+            .strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix('_'))
+            .is_some_and(|v| v.parse::<Version>().is_ok());
+        if !bare && !versioned {
+            continue;
+        }
+
+        found.push(InstalledArtifact { path: entry.path(), kind });
+    }
+
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(found)
+}
+
+/// Newest installed version of every mod in `mods_dir`, which is the one the game loads.
+///
+/// Versions come from `name_<version>` file names; unversioned folders and links are read from their info.json.
+pub fn installed_versions(mods_dir: &Path) -> Result<BTreeMap<String, Version>> {
+    let mut versions: BTreeMap<String, Version> = BTreeMap::new();
+    if !mods_dir.exists() {
+        return Ok(versions);
+    }
+
+    for entry in fs::read_dir(mods_dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let is_dir = entry.path().is_dir();
+        let stem = match file_name.strip_suffix(".zip") {
+            Some(stem) if !is_dir => stem,
+            _ if is_dir => file_name.as_str(),
+            _ => continue,
+        };
+
+        let from_name = stem
+            .rsplit_once('_')
+            .and_then(|(name, version)| Some((name.to_string(), version.parse::<Version>().ok()?)));
+        let found = match from_name {
+            Some(found) => Some(found),
+            None if is_dir => Info::load_from_dir(&entry.path()).ok().and_then(|info| Some((info.name, info.version.parse().ok()?))),
+            None => None,
+        };
+        if let Some((name, version)) = found {
+            let newest = versions.entry(name).or_insert(version);
+            *newest = (*newest).max(version);
+        }
+    }
+    Ok(versions)
+}
+
+/// Delete an installed artifact; symlinks are removed without touching their target
+pub fn remove_artifact(artifact: &InstalledArtifact) -> Result<()> {
+    let result = match artifact.kind {
+        ArtifactKind::Zip => fs::remove_file(&artifact.path),
+        ArtifactKind::Folder => fs::remove_dir_all(&artifact.path),
+        // Directory symlinks are files on Unix but directories on Windows
+        ArtifactKind::Symlink => fs::remove_file(&artifact.path).or_else(|_| fs::remove_dir(&artifact.path)),
+    };
+    result.with_context(|| format!("Failed to remove {}", artifact.path.display()))
+}
@@ -1,48 +1,251 @@
 use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::config::BuildConfig;
-use crate::mod_info::{resolve_mod_paths, Info};
-use crate::platform::factorio_mods_dir;
-use crate::zip_builder::build_zip;
+use crate::build::{build_mods, BuiltMod};
+use crate::compat::{dependency_problems, game_compatibility, InstalledSet};
+use crate::config::Settings;
+use crate::installed::{find_installed, installed_versions, remove_artifact, ArtifactKind, InstalledArtifact};
+use crate::locate::builtin_mods;
+use crate::mod_list::{ModList, MOD_LIST_FILE};
+use crate::platform::InstallTarget;
+use crate::workspace::{select_mods, WorkspaceMod};
+
+/// Folder next to the mods directory that `--replace backup` moves old versions into
+const BACKUP_DIR: &str = "mods-backup";
+
+/// What to do with other installed versions of a mod when installing a new one
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReplacePolicy {
+    /// Delete other zips, folders and links of the mod
+    Remove,
+    /// Move them into a `mods-backup` folder next to the mods directory
+    Backup,
+    /// Leave them in place
+    Keep,
+}
 
 /// Main installation function - coordinates the entire process
-pub fn install_mods(mod_path: Option<PathBuf>, out_dir: String, config: BuildConfig) -> Result<()> {
-    let cwd = std::env::current_dir()?;
-    let mods = resolve_mod_paths(mod_path, &cwd)?;
-    
-    if mods.is_empty() {
-        bail!("No mods found. Place an info.json in the repo root or in subfolders.");
+pub fn install_mods(mod_path: Option<PathBuf>, settings: &Settings, replace: ReplacePolicy, enable: bool, force: bool) -> Result<()> {
+    let targets = settings.targets()?;
+    let built = build_mods(mod_path, settings)?;
+    let mods: Vec<&WorkspaceMod> = built.iter().map(|b| &b.module).collect();
+    check_game_versions(&built, &targets, settings, force)?;
+
+    for target in &targets {
+        settings.log(&format!("🎯 Target {} → {}", target.name, target.mods_dir.display()));
+        for built in &built {
+            install_one(built, &target.mods_dir, replace, force)?;
+        }
+        if enable {
+            set_mods_enabled(&target.mods_dir, &mods, true, true)?;
+        }
+        report_dependencies(&mods, target)?;
+    }
+    Ok(())
+}
+
+/// Warn about dependencies of freshly installed mods that the target mods directory doesn't satisfy
+fn report_dependencies(mods: &[&WorkspaceMod], target: &InstallTarget) -> Result<()> {
+    let versions = installed_versions(&target.mods_dir)?;
+    let mod_list = ModList::load(&target.mods_dir)?;
+    let builtin = builtin_mods(target.game_dir.as_deref(), target.game_version);
+    let installed = InstalledSet { versions: &versions, mod_list: mod_list.as_ref(), builtin: &builtin, game: target.game_version };
+
+    for m in mods {
+        for problem in dependency_problems(&m.info, &installed)? {
+            println!("⚠️  {} (target {})", problem, target.name);
+        }
+    }
+    Ok(())
+}
+
+/// Refuse to install mods the targets' game would not load, or only warn about them with `force`
+fn check_game_versions(built: &[BuiltMod], targets: &[InstallTarget], settings: &Settings, force: bool) -> Result<()> {
+    let mut problems = Vec::new();
+    for target in targets {
+        let Some(game) = target.game_version else {
+            settings.log(&format!("ℹ️  Game version of target {} unknown, skipping compatibility check", target.name));
+            continue;
+        };
+        for b in built {
+            for problem in game_compatibility(&b.module.info, game)? {
+                problems.push(format!("{} (target {})", problem, target.name));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    if force {
+        for problem in &problems {
+            println!("⚠️  {}", problem);
+        }
+        return Ok(());
+    }
+    bail!("Incompatible with the target game:\n  {}\nPass --force to install anyway", problems.join("\n  "))
+}
+
+/// Enable or disable the selected mods in each target's mod-list.json
+pub fn toggle_mods(mod_path: Option<PathBuf>, settings: &Settings, enabled: bool, with_dependencies: bool) -> Result<()> {
+    let targets = settings.targets()?;
+    let mods = select_mods(mod_path, settings)?;
+    let mods: Vec<&WorkspaceMod> = mods.iter().collect();
+    for target in &targets {
+        set_mods_enabled(&target.mods_dir, &mods, enabled, with_dependencies)?;
+    }
+    Ok(())
+}
+
+/// Flip the mods' entries in mod-list.json. When enabling with `with_dependencies`, also enable
+/// required dependencies that the list knows about but has disabled.
+fn set_mods_enabled(mods_dir: &Path, mods: &[&WorkspaceMod], enabled: bool, with_dependencies: bool) -> Result<()> {
+    fs::create_dir_all(mods_dir)?;
+    let mut mod_list = ModList::load_or_new(mods_dir)?;
+    let verb = if enabled { "Enabled" } else { "Disabled" };
+
+    let mut changed = false;
+    for m in mods {
+        if mod_list.set_enabled(&m.info.name, enabled) {
+            println!("📝 {} {}", verb, m.info.name);
+            changed = true;
+        }
+        if !(enabled && with_dependencies) {
+            continue;
+        }
+        for dep in m.info.parsed_dependencies()?.into_iter().filter(|d| d.is_required()) {
+            if mod_list.get(&dep.name).is_some_and(|entry| !entry.enabled) {
+                mod_list.set_enabled(&dep.name, true);
+                println!("📝 Enabled {} (required by {})", dep.name, m.info.name);
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        mod_list.save()?;
+    } else {
+        println!("ℹ️  {} already up to date", mods_dir.join(MOD_LIST_FILE).display());
+    }
+    Ok(())
+}
+
+/// Remove every installed zip and link of the selected mods and drop them from each target's mod-list.json.
+/// Plain folders are only removed with `force`, and never when they hold the mod's source.
+pub fn uninstall_mods(mod_path: Option<PathBuf>, settings: &Settings, force: bool) -> Result<()> {
+    let targets = settings.targets()?;
+    let mods = select_mods(mod_path, settings)?;
+    for target in &targets {
+        uninstall_from(&target.mods_dir, &mods, force)?;
     }
+    Ok(())
+}
 
-    let out_dir = PathBuf::from(out_dir);
-    fs::create_dir_all(&out_dir)?;
+fn uninstall_from(mods_dir: &Path, mods: &[WorkspaceMod], force: bool) -> Result<()> {
+    let mut mod_list = ModList::load(mods_dir)?;
 
-    for mod_path in mods {
-        config.log(&format!("🔍 Processing mod at {}", mod_path.display()));
-        install_one(&mod_path, &out_dir, &config)?;
+    for m in mods {
+        let artifacts = find_installed(mods_dir, &m.info.name)?;
+        for artifact in &artifacts {
+            if artifact.holds(&m.root) {
+                println!("⏭️  Kept {}: it holds the mod's source", artifact.path.display());
+                continue;
+            }
+            if artifact.kind == ArtifactKind::Folder && !artifact.is_dev_copy() && !force {
+                println!("⏭️  Kept {}: not created by cargo factorio (pass --force to delete it)", artifact.path.display());
+                continue;
+            }
+            remove_artifact(artifact)?;
+            println!("🗑️  Removed {}", artifact.path.display());
+        }
+
+        let listed = mod_list.as_mut().is_some_and(|list| list.remove(&m.info.name));
+        if listed {
+            println!("📝 Removed {} from {}", m.info.name, MOD_LIST_FILE);
+        }
+        if artifacts.is_empty() && !listed {
+            println!("ℹ️  {} is not installed in {}", m.info.name, mods_dir.display());
+        }
     }
 
+    if let Some(list) = mod_list {
+        list.save()?;
+    }
     Ok(())
 }
 
-/// Install one mod: build zip into out_dir, copy to Factorio/mods, keep the built zip.
-fn install_one(mod_root: &Path, out_dir: &Path, config: &BuildConfig) -> Result<()> {
-    let info = Info::load_from_dir(mod_root)
-        .context("Failed to parse info.json")?;
-    
-    let zip_name = info.zip_name();
-    let zip_path = out_dir.join(format!("{}.zip", zip_name));
+/// Install one built mod: copy its zip into the mods directory, then clear out stale versions. The built zip is kept.
+fn install_one(built: &BuiltMod, mods_dir: &Path, replace: ReplacePolicy, force: bool) -> Result<()> {
+    fs::create_dir_all(mods_dir)?;
+
+    // Copy under a temporary name first so a failed copy never leaves a half-written zip or nothing installed
+    let dest = mods_dir.join(built.zip_path.file_name().unwrap());
+    let partial = dest.with_extension("zip.part");
+    fs::copy(&built.zip_path, &partial).with_context(|| format!("Failed to copy {} to {}", built.zip_path.display(), partial.display()))?;
+    fs::rename(&partial, &dest).with_context(|| format!("Failed to move {} into place", partial.display()))?;
+    replace_stale_versions(mods_dir, &built.module.info.name, &dest, replace, Some(&built.module.root), force)?;
 
-    build_zip(mod_root, &zip_path, &zip_name, config)?;
-    
-    let mods_dir = factorio_mods_dir()?;
-    fs::create_dir_all(&mods_dir)?;
-    
-    let dest = mods_dir.join(zip_path.file_name().unwrap());
-    fs::copy(&zip_path, &dest)?;
-    
-    println!("✅ Installed {} → {}", zip_name, dest.display());
+    println!("✅ Installed {} → {}", built.module.info.zip_name(), dest.display());
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Remove or back up every installed artifact of `name` other than `dest`, listing each one.
+/// Artifacts holding `source` (a mod developed inside the mods folder) are left alone, and so are folders and
+/// links the tool did not create (such as a git checkout of the mod) unless `force` is set.
+pub fn replace_stale_versions(
+    mods_dir: &Path,
+    name: &str,
+    dest: &Path,
+    replace: ReplacePolicy,
+    source: Option<&Path>,
+    force: bool,
+) -> Result<()> {
+    if replace == ReplacePolicy::Keep {
+        return Ok(());
+    }
+
+    for artifact in find_installed(mods_dir, name)? {
+        if artifact.path == dest {
+            continue;
+        }
+        if source.is_some_and(|root| artifact.holds(root)) {
+            println!("⏭️  Kept {}: it holds the mod's source", artifact.path.display());
+            continue;
+        }
+        if artifact.kind != ArtifactKind::Zip && !artifact.is_link_of(source) && !force {
+            println!("⚠️  Kept {}: not created by cargo factorio; remove it if it is stale", artifact.path.display());
+            continue;
+        }
+        match replace {
+            ReplacePolicy::Remove => {
+                remove_artifact(&artifact)?;
+                println!("🗑️  Removed {}", artifact.path.display());
+            }
+            ReplacePolicy::Backup => {
+                let backup = back_up_artifact(mods_dir, &artifact)?;
+                println!("🗄️  Backed up {} → {}", artifact.path.display(), backup.display());
+            }
+            ReplacePolicy::Keep => {}
+        }
+    }
+    Ok(())
+}
+
+/// Move an artifact into the backup folder, replacing an older backup of the same name
+fn back_up_artifact(mods_dir: &Path, artifact: &InstalledArtifact) -> Result<PathBuf> {
+    let backup_dir = mods_dir.parent().unwrap_or(mods_dir).join(BACKUP_DIR);
+    fs::create_dir_all(&backup_dir)?;
+
+    let target = backup_dir.join(artifact.path.file_name().unwrap());
+    if let Ok(meta) = fs::symlink_metadata(&target) {
+        if meta.is_dir() {
+            fs::remove_dir_all(&target)?;
+        } else {
+            fs::remove_file(&target)?;
+        }
+    }
+    fs::rename(&artifact.path, &target).with_context(|| format!("Failed to move {} to {}", artifact.path.display(), target.display()))?;
+    Ok(target)
+}
@@ -0,0 +1,101 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::{BuildConfig, Settings};
+use crate::installed::{find_installed, remove_artifact, ArtifactKind, COPY_MARKER};
+use crate::workspace::{select_mods, WorkspaceMod};
+use crate::zip_builder::mod_entries;
+
+/// Link each selected mod's source folder into every target's mods folder as an unzipped `<name>` folder
+pub fn link_mods(mod_path: Option<PathBuf>, settings: &Settings) -> Result<()> {
+    let targets = settings.targets()?;
+    for m in select_mods(mod_path, settings)? {
+        for target in &targets {
+            fs::create_dir_all(&target.mods_dir)?;
+            link_one(&m, &target.mods_dir, &settings.build_config(&m.info.name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove dev links (or copied dev folders) created by `link_mods`
+pub fn unlink_mods(mod_path: Option<PathBuf>, settings: &Settings) -> Result<()> {
+    let targets = settings.targets()?;
+
+    for m in select_mods(mod_path, settings)? {
+        let source = fs::canonicalize(&m.root)?;
+        let mut removed = false;
+
+        let installed = targets.iter().map(|t| find_installed(&t.mods_dir, &m.info.name)).collect::<Result<Vec<_>>>()?;
+        for artifact in installed.into_iter().flatten() {
+            if artifact.is_link_of(Some(&source)) {
+                remove_artifact(&artifact)?;
+                println!("✂️  Unlinked {}", artifact.path.display());
+                removed = true;
+            }
+        }
+
+        if !removed {
+            println!("ℹ️  {} is not linked", m.info.name);
+        }
+    }
+    Ok(())
+}
+
+/// Replace installed zips and earlier links of the mod with a symlink to its source, or a copy if linking fails.
+/// Folders the tool did not create are never touched.
+fn link_one(m: &WorkspaceMod, mods_dir: &Path, config: &BuildConfig) -> Result<()> {
+    let source = fs::canonicalize(&m.root)?;
+    let dest = mods_dir.join(&m.info.name);
+
+    let installed = find_installed(mods_dir, &m.info.name)?;
+    if let Some(home) = installed.iter().find(|a| a.holds(&source)) {
+        println!("ℹ️  {} already lives in {}, nothing to link", m.info.name, home.path.display());
+        return Ok(());
+    }
+    if let Some(foreign) = installed.iter().find(|a| a.kind != ArtifactKind::Zip && !a.is_link_of(Some(&source))) {
+        bail!("{} was not created by `cargo factorio link`; move it out of the mods folder first", foreign.path.display());
+    }
+    for artifact in &installed {
+        remove_artifact(artifact)?;
+        config.log(&format!("🗑️  Removed {}", artifact.path.display()));
+    }
+
+    match symlink_dir(&source, &dest) {
+        Ok(()) => println!("🔗 Linked {} → {}", dest.display(), source.display()),
+        Err(e) => {
+            config.log(&format!("⚠️  Could not symlink ({}), copying instead", e));
+            copy_mod(&m.root, &dest, config)?;
+            println!("📂 Copied {} → {} (re-run link after changes)", source.display(), dest.display());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink_dir(source: &Path, dest: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(source, dest)
+}
+
+#[cfg(windows)]
+fn symlink_dir(source: &Path, dest: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_dir(source, dest)
+}
+
+/// Copy the files that would be packed into the zip, marking the folder as a dev copy
+fn copy_mod(mod_root: &Path, dest: &Path, config: &BuildConfig) -> Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in mod_entries(mod_root, config)? {
+        let rel = entry.path().strip_prefix(mod_root)?;
+        let target = dest.join(rel);
+        if entry.file_type().is_some_and(|t| t.is_dir()) {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target).with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+        }
+    }
+    fs::write(dest.join(COPY_MARKER), "Created by `cargo factorio link`; removed by `cargo factorio unlink`.\n")?;
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::diagnostics::Diagnostic;
+
+/// Folder holding one subfolder of `.cfg` files per language
+pub const LOCALE_DIR: &str = "locale";
+
+/// Language every other one is compared against
+pub const REFERENCE_LANGUAGE: &str = "en";
+
+/// One `key=value` line of a locale file
+pub struct LocaleEntry {
+    /// `[section]` the key is in; empty for keys above the first section
+    pub section: String,
+    pub key: String,
+    /// Value with `\n` escapes turned into newlines
+    pub value: String,
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+impl LocaleEntry {
+    /// How the key is written in a localised string, e.g. `item-name.iron-plate`
+    pub fn full_key(&self) -> String {
+        if self.section.is_empty() { self.key.clone() } else { format!("{}.{}", self.section, self.key) }
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (self.line, 1)
+    }
+}
+
+/// All entries of one language, from every `.cfg` file in its folder
+pub struct Language {
+    pub code: String,
+    pub entries: Vec<LocaleEntry>,
+}
+
+impl Language {
+    /// Entries by full key; the first definition wins like in the game
+    pub fn by_key(&self) -> BTreeMap<String, &LocaleEntry> {
+        let mut keys = BTreeMap::new();
+        for entry in &self.entries {
+            keys.entry(entry.full_key()).or_insert(entry);
+        }
+        keys
+    }
+}
+
+/// Parse one `.cfg` file into `entries`, reporting lines the game would not understand
+pub fn parse_cfg(source: &str, file: &Path, entries: &mut Vec<LocaleEntry>, diagnostics: &mut Vec<Diagnostic>) {
+    let mut section = String::new();
+    for (i, raw) in source.lines().enumerate() {
+        let number = i + 1;
+        let line = raw.trim_end_matches('\r');
+        if line.trim().is_empty() || line.trim_start().starts_with([';', '#']) {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix('[') {
+            match rest.strip_suffix(']') {
+                Some(name) if !name.trim().is_empty() => section = name.trim().to_string(),
+                _ => diagnostics.push(Diagnostic::error(file, (number, 1), "malformed section header; expected `[section-name]`")),
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            diagnostics.push(Diagnostic::error(file, (number, 1), "expected `key=value`, `[section]` or a `;` comment"));
+            continue;
+        };
+        if key.trim().is_empty() {
+            diagnostics.push(Diagnostic::error(file, (number, 1), "empty key"));
+            continue;
+        }
+        entries.push(LocaleEntry {
+            section: section.clone(),
+            key: key.trim().to_string(),
+            value: value.replace("\\n", "\n"),
+            file: file.to_path_buf(),
+            line: number,
+        });
+    }
+}
+
+/// Every language under `locale/`, sorted by code, plus problems found while reading the files
+pub fn load_languages(mod_root: &Path) -> Result<(Vec<Language>, Vec<Diagnostic>)> {
+    let mut languages = Vec::new();
+    let mut diagnostics = Vec::new();
+    let locale_dir = mod_root.join(LOCALE_DIR);
+    if !locale_dir.is_dir() {
+        return Ok((languages, diagnostics));
+    }
+
+    let mut dirs: Vec<PathBuf> = fs::read_dir(&locale_dir)?.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()).collect();
+    dirs.sort();
+    for dir in dirs {
+        let mut files: Vec<PathBuf> = fs::read_dir(&dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "cfg"))
+            .collect();
+        files.sort();
+
+        let mut entries = Vec::new();
+        for file in files {
+            match String::from_utf8(fs::read(&file)?) {
+                Ok(source) => parse_cfg(source.strip_prefix('\u{feff}').unwrap_or(&source), &file, &mut entries, &mut diagnostics),
+                Err(e) => {
+                    let offset = e.utf8_error().valid_up_to();
+                    let line = e.as_bytes()[..offset].iter().filter(|b| **b == b'\n').count() + 1;
+                    diagnostics.push(Diagnostic::error(&file, (line, 1), "invalid UTF-8; locale files must be saved as UTF-8"));
+                }
+            }
+        }
+        let code = dir.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        languages.push(Language { code, entries });
+    }
+    Ok((languages, diagnostics))
+}
+
+/// Check every language for duplicates and markup problems, and against `en` for missing, extra and
+/// mismatched keys
+pub fn validate_locales(mod_root: &Path) -> Result<Vec<Diagnostic>> {
+    let (languages, mut diagnostics) = load_languages(mod_root)?;
+
+    for language in &languages {
+        let mut seen: BTreeMap<String, &LocaleEntry> = BTreeMap::new();
+        for entry in &language.entries {
+            if let Some(first) = seen.get(&entry.full_key()) {
+                diagnostics.push(Diagnostic::error(
+                    &entry.file,
+                    entry.position(),
+                    format!("duplicate key `{}` (first defined at {}:{})", entry.full_key(), first.file.display(), first.line),
+                ));
+            } else {
+                seen.insert(entry.full_key(), entry);
+            }
+            if let Some(problem) = unbalanced_rich_text(&entry.value) {
+                diagnostics.push(Diagnostic::warning(&entry.file, entry.position(), format!("`{}`: {}", entry.full_key(), problem)));
+            }
+        }
+    }
+
+    let Some(reference) = languages.iter().find(|l| l.code == REFERENCE_LANGUAGE) else {
+        if !languages.is_empty() {
+            let dir = mod_root.join(LOCALE_DIR);
+            diagnostics.push(Diagnostic::warning(&dir, (1, 1), "no `en` locale to compare the other languages against"));
+        }
+        return Ok(diagnostics);
+    };
+    let reference_keys = reference.by_key();
+
+    for language in languages.iter().filter(|l| l.code != REFERENCE_LANGUAGE) {
+        let keys = language.by_key();
+        for (key, entry) in &keys {
+            let Some(original) = reference_keys.get(key) else {
+                diagnostics.push(Diagnostic::warning(&entry.file, entry.position(), format!("`{}` is not defined in `en`", key)));
+                continue;
+            };
+            let (expected, found) = (parameters(&original.value), parameters(&entry.value));
+            if expected != found {
+                diagnostics.push(Diagnostic::error(
+                    &entry.file,
+                    entry.position(),
+                    format!("`{}` uses parameters {} but `en` uses {}", key, describe(&found), describe(&expected)),
+                ));
+            }
+        }
+        for (key, original) in &reference_keys {
+            if !keys.contains_key(key) {
+                diagnostics.push(Diagnostic::warning(
+                    &original.file,
+                    original.position(),
+                    format!("`{}` has no `{}` translation", key, language.code),
+                ));
+            }
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// Numbers of the `__1__`-style parameters a value refers to, including plural forms
+fn parameters(value: &str) -> BTreeSet<u32> {
+    let mut found = BTreeSet::new();
+    for (i, _) in value.match_indices("__") {
+        let rest = &value[i + 2..];
+        // `__N__`, or `__plural_for_parameter_N_{...}__`
+        let (rest, close) = match rest.strip_prefix("plural_for_parameter_") {
+            Some(plural) => (plural, "_{"),
+            None => (rest, "__"),
+        };
+        let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+        let closed = rest[digits.len()..].starts_with(close);
+        if !digits.is_empty() && closed && let Ok(n) = digits.parse() {
+            found.insert(n);
+        }
+    }
+    found
+}
+
+fn describe(parameters: &BTreeSet<u32>) -> String {
+    if parameters.is_empty() {
+        return "none".to_string();
+    }
+    parameters.iter().map(|n| format!("__{}__", n)).collect::<Vec<_>>().join(", ")
+}
+
+/// A problem with `[color=...]`/`[font=...]` rich text tags that need a matching closing tag
+fn unbalanced_rich_text(value: &str) -> Option<String> {
+    let mut open: Vec<&str> = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('[') {
+        let Some(end) = rest[start..].find(']') else { break };
+        let tag = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            if open.pop() != Some(name) {
+                return Some(format!("`[/{}]` does not close an open `[{}=...]` tag", name, name));
+            }
+        } else if let Some((name @ ("color" | "font"), _)) = tag.split_once('=') {
+            open.push(name);
+        }
+    }
+    open.pop().map(|name| format!("`[{}=...]` is never closed with `[/{}]`", name, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parameters_match_plain_and_plural_forms_only() {
+        let found = parameters("__1__ and __plural_for_parameter_2_{1=one|rest=many}__ but not __3_x or __4 or __plural_for_parameter_5__");
+        assert_eq!(found.into_iter().collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn parse_cfg_trims_keys_and_indented_comments() {
+        let source = "top=level\r\n[item-name]\n  ; indented comment\n\t# another\n iron-plate = Iron plate\\nsecond line\n=missing key\n";
+        let (mut entries, mut diagnostics) = (Vec::new(), Vec::new());
+        parse_cfg(source, Path::new("en/mod.cfg"), &mut entries, &mut diagnostics);
+
+        let keys: Vec<_> = entries.iter().map(|e| (e.full_key(), e.value.as_str(), e.line)).collect();
+        assert_eq!(keys, [("top".to_string(), "level", 1), ("item-name.iron-plate".to_string(), " Iron plate\nsecond line", 5)]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].message.as_str()), (6, "empty key"));
+    }
+}
@@ -0,0 +1,483 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::BuildConfig;
+use crate::diagnostics::{position_of, Diagnostic};
+use crate::locale::{load_languages, REFERENCE_LANGUAGE};
+use crate::zip_builder::mod_entries;
+
+/// Files the game runs first in the settings and data stages, where prototypes are defined
+const PROTOTYPE_STAGE_ENTRY_POINTS: &[&str] =
+    &["settings.lua", "settings-updates.lua", "settings-final-fixes.lua", "data.lua", "data-updates.lua", "data-final-fixes.lua"];
+
+/// File the game runs first in the control stage
+const CONTROL_ENTRY_POINT: &str = "control.lua";
+
+const ITEM_TYPES: &[&str] = &[
+    "item",
+    "ammo",
+    "armor",
+    "blueprint",
+    "blueprint-book",
+    "capsule",
+    "copy-paste-tool",
+    "deconstruction-item",
+    "gun",
+    "item-with-entity-data",
+    "item-with-inventory",
+    "item-with-label",
+    "item-with-tags",
+    "module",
+    "rail-planner",
+    "repair-tool",
+    "selection-tool",
+    "space-platform-starter-pack",
+    "spidertron-remote",
+    "tool",
+    "upgrade-item",
+];
+
+const SETTING_TYPES: &[&str] = &["bool-setting", "int-setting", "double-setting", "string-setting", "color-setting"];
+
+/// Name sections without a fallback: the game shows "Unknown key" when a prototype has no name there
+const REQUIRED_NAME_SECTIONS: &[&str] =
+    &["item-name", "fluid-name", "technology-name", "mod-setting-name", "controls", "virtual-signal-name", "item-group-name"];
+
+/// Fields that give a prototype its name some other way than its own name key
+const NAMED_ELSEWHERE_FIELDS: &[&str] = &["localised_name", "place_result", "place_as_tile", "place_as_equipment_result"];
+
+/// Fields that mark a `type`/`name` table as an ingredient or product rather than a prototype
+const AMOUNT_FIELDS: &[&str] = &["amount", "amount_min", "amount_max", "probability"];
+
+/// `__TAG__name__` references a locale value can make to another prototype's name
+const VALUE_REFERENCES: &[(&str, &str)] = &[
+    ("__ITEM__", "item-name"),
+    ("__ENTITY__", "entity-name"),
+    ("__FLUID__", "fluid-name"),
+    ("__TILE__", "tile-name"),
+    ("__CONTROL__", "controls"),
+];
+
+/// What one Lua file contributes to the cross-reference
+#[derive(Default)]
+struct LuaFile {
+    path: PathBuf,
+    /// Literal localised-string keys such as `{"item-name.foo"}`, with their position
+    references: Vec<(String, (usize, usize))>,
+    /// Sections of keys built at runtime, such as `{"my-gui." .. name}`
+    dynamic_sections: BTreeSet<String>,
+    prototypes: Vec<Prototype>,
+    /// Modules passed to `require`
+    requires: Vec<String>,
+}
+
+/// A `{type = "...", name = "..."}` table that looks like a prototype definition
+struct Prototype {
+    kind: String,
+    /// Literal name, or the literal start of a computed one like `"foo-" .. tier`
+    name: String,
+    dynamic: bool,
+    /// Hidden, or named through `localised_name` or the entity/tile/equipment it places
+    named_elsewhere: bool,
+    position: (usize, usize),
+}
+
+/// A table constructor being scanned
+#[derive(Default)]
+struct Table {
+    offset: usize,
+    /// Opened as the value of a field, like `ingredients = {`
+    is_field: bool,
+    fields: BTreeSet<String>,
+    /// Fields set to a string literal
+    strings: BTreeMap<String, String>,
+    dynamic_name: Option<String>,
+    hidden: bool,
+}
+
+/// The bits of Lua syntax the scan cares about
+#[derive(Debug, PartialEq)]
+enum Token {
+    Str(String),
+    Name(String),
+    Concat,
+    Assign,
+    Open,
+    Close,
+    Punct(char),
+}
+
+/// Cross-reference the literal localised strings and prototype names in the Lua files the zip would
+/// contain with the `en` locale, reporting keys that are referenced but undefined, and defined but unused
+pub fn validate_locale_references(mod_root: &Path, mod_name: &str, config: &BuildConfig) -> Result<Vec<Diagnostic>> {
+    let mut files = BTreeMap::new();
+    for entry in mod_entries(mod_root, config)? {
+        let path = entry.path();
+        if !entry.file_type().is_some_and(|t| t.is_file()) || path.extension().is_none_or(|ext| ext != "lua") {
+            continue;
+        }
+        let Ok(relative) = path.strip_prefix(mod_root) else { continue };
+        let relative = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        let source = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+        files.insert(relative, LuaFile { path: path.to_path_buf(), ..scan_lua(&source) });
+    }
+    if files.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (languages, _) = load_languages(mod_root)?;
+    let defined = languages.iter().find(|l| l.code == REFERENCE_LANGUAGE).map(|l| l.by_key()).unwrap_or_default();
+
+    // Tables built only by control scripts are GUI elements and the like, not prototypes
+    let prototype_stage = reachable(&files, PROTOTYPE_STAGE_ENTRY_POINTS, mod_name);
+    let control_only: BTreeSet<_> = reachable(&files, &[CONTROL_ENTRY_POINT], mod_name).difference(&prototype_stage).cloned().collect();
+    let prototypes: Vec<(&Path, &Prototype)> = files
+        .iter()
+        .filter(|(relative, _)| !control_only.contains(*relative))
+        .flat_map(|(_, file)| file.prototypes.iter().map(|p| (file.path.as_path(), p)))
+        .collect();
+
+    let mut diagnostics = Vec::new();
+    for (file, prototype) in prototypes.iter().filter(|(_, p)| !p.dynamic && !p.named_elsewhere) {
+        let (section, _) = prototype_sections(&prototype.kind);
+        let key = format!("{}.{}", section, prototype.name);
+        let fallback = format!("{}.{}", section, technology_base(&prototype.kind, &prototype.name));
+        if REQUIRED_NAME_SECTIONS.contains(&section) && !defined.contains_key(&key) && !defined.contains_key(&fallback) {
+            let message = format!("{} `{}` has no `{}` in the `en` locale", prototype.kind, prototype.name, key);
+            diagnostics.push(Diagnostic::warning(file, prototype.position, message));
+        }
+    }
+
+    // Only report literal keys this mod is expected to define, not the game's own
+    let en_sections: BTreeSet<&str> = defined.values().map(|e| e.section.as_str()).collect();
+    for file in files.values() {
+        for (key, position) in &file.references {
+            let Some((section, name)) = key.split_once('.') else { continue };
+            let own_prototype = prototypes.iter().any(|(_, p)| {
+                let (name_section, description_section) = prototype_sections(&p.kind);
+                !p.dynamic && p.name == name && (section == name_section || section == description_section)
+            });
+            let own_section = en_sections.contains(section) && !is_prototype_section(section);
+            if !defined.contains_key(key) && (own_prototype || own_section) {
+                diagnostics.push(Diagnostic::warning(&file.path, *position, format!("`{}` is not defined in the `en` locale", key)));
+            }
+        }
+    }
+
+    let mut used: BTreeSet<String> = files.values().flat_map(|f| f.references.iter().map(|(key, _)| key.clone())).collect();
+    let mut attributed: BTreeSet<String> = used.iter().filter_map(|key| key.split_once('.')).map(|(s, _)| s.to_string()).collect();
+    let mut prefixes = Vec::new();
+    for (_, prototype) in &prototypes {
+        let (name_section, description_section) = prototype_sections(&prototype.kind);
+        for section in [name_section, description_section] {
+            attributed.insert(section.to_string());
+            if prototype.dynamic {
+                prefixes.push(format!("{}.{}", section, prototype.name));
+            } else {
+                used.insert(format!("{}.{}", section, prototype.name));
+                used.insert(format!("{}.{}", section, technology_base(&prototype.kind, &prototype.name)));
+            }
+        }
+    }
+    for entry in defined.values() {
+        used.extend(value_references(&entry.value));
+    }
+
+    let dynamic: BTreeSet<&str> = files.values().flat_map(|f| f.dynamic_sections.iter().map(String::as_str)).collect();
+    for (key, entry) in &defined {
+        let section = entry.section.as_str();
+        if !attributed.contains(section) || dynamic.contains(section) || used.contains(key) || prefixes.iter().any(|p| key.starts_with(p.as_str())) {
+            continue;
+        }
+        let message = format!("`{}` is not used by any prototype or localised string in the Lua files", key);
+        diagnostics.push(Diagnostic::warning(&entry.file, entry.position(), message));
+    }
+    Ok(diagnostics)
+}
+
+/// Locale sections holding a prototype's name and description, by prototype type
+fn prototype_sections(kind: &str) -> (&'static str, &'static str) {
+    match kind {
+        _ if ITEM_TYPES.contains(&kind) => ("item-name", "item-description"),
+        _ if SETTING_TYPES.contains(&kind) => ("mod-setting-name", "mod-setting-description"),
+        _ if kind.ends_with("-equipment") => ("equipment-name", "equipment-description"),
+        _ if kind.ends_with("achievement") => ("achievement-name", "achievement-description"),
+        "fluid" => ("fluid-name", "fluid-description"),
+        "recipe" => ("recipe-name", "recipe-description"),
+        "technology" => ("technology-name", "technology-description"),
+        "tile" => ("tile-name", "tile-description"),
+        "virtual-signal" => ("virtual-signal-name", "virtual-signal-description"),
+        "item-group" => ("item-group-name", "item-group-description"),
+        "custom-input" => ("controls", "controls-description"),
+        "shortcut" => ("shortcut-name", "shortcut-description"),
+        "ammo-category" => ("ammo-category-name", "ammo-category-description"),
+        "fuel-category" => ("fuel-category-name", "fuel-category-description"),
+        "autoplace-control" => ("autoplace-control-names", "autoplace-control-description"),
+        "planet" | "space-location" => ("space-location-name", "space-location-description"),
+        "quality" => ("quality-name", "quality-description"),
+        "surface" => ("surface-name", "surface-description"),
+        "asteroid-chunk" => ("asteroid-chunk-name", "asteroid-chunk-description"),
+        "tips-and-tricks-item" => ("tips-and-tricks-item-name", "tips-and-tricks-item-description"),
+        // Entities come in far too many types to list; anything else is treated as one
+        _ => ("entity-name", "entity-description"),
+    }
+}
+
+/// Whether the game fills `section` from prototype names, so the mod only owns the keys of its own prototypes
+fn is_prototype_section(section: &str) -> bool {
+    section == "controls" || section.ends_with("-name") || section.ends_with("-names") || section.ends_with("-description")
+}
+
+/// Name without a trailing `-N` level, which leveled technologies fall back to
+fn technology_base<'a>(kind: &str, name: &'a str) -> &'a str {
+    match name.rsplit_once('-') {
+        Some((base, level)) if kind == "technology" && level.parse::<u32>().is_ok() => base,
+        _ => name,
+    }
+}
+
+/// Keys named by `__ITEM__foo__`-style references in a locale value
+fn value_references(value: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    for (tag, section) in VALUE_REFERENCES {
+        for (i, _) in value.match_indices(tag) {
+            let rest = &value[i + tag.len()..];
+            if let Some(end) = rest.find("__") {
+                keys.push(format!("{}.{}", section, &rest[..end]));
+            }
+        }
+    }
+    keys
+}
+
+/// Files `require`d, directly or not, from the given entry points
+fn reachable(files: &BTreeMap<String, LuaFile>, entry_points: &[&str], mod_name: &str) -> BTreeSet<String> {
+    let mut seen = BTreeSet::new();
+    let mut queue: VecDeque<String> = entry_points.iter().map(|f| f.to_string()).collect();
+    while let Some(relative) = queue.pop_front() {
+        let Some(file) = files.get(&relative) else { continue };
+        if !seen.insert(relative.clone()) {
+            continue;
+        }
+        queue.extend(file.requires.iter().filter_map(|module| resolve_require(&relative, module, mod_name, files)));
+    }
+    seen
+}
+
+/// File a `require` in `from` loads: `a.b`, `a/b` and `a/b.lua` relative to `from` or the mod root, or
+/// `__mod-name__.a.b` within this mod
+fn resolve_require(from: &str, module: &str, mod_name: &str, files: &BTreeMap<String, LuaFile>) -> Option<String> {
+    let module = match module.strip_prefix(&format!("__{}__", mod_name)) {
+        Some(rest) => rest.trim_start_matches(['.', '/']),
+        None if module.starts_with("__") => return None,
+        None => module,
+    };
+    let module = format!("{}.lua", module.strip_suffix(".lua").unwrap_or(module).replace('.', "/"));
+    let relative = match from.rsplit_once('/') {
+        Some((dir, _)) => format!("{}/{}", dir, module),
+        None => module.clone(),
+    };
+    [relative, module].into_iter().find(|candidate| files.contains_key(candidate))
+}
+
+/// Collect localised-string keys, prototype tables and requires from Lua source
+fn scan_lua(source: &str) -> LuaFile {
+    let tokens = tokenize(source);
+    let token = |k: usize| tokens.get(k).map(|(t, _)| t);
+    let mut file = LuaFile::default();
+    let mut tables: Vec<Table> = Vec::new();
+
+    for (k, (current, offset)) in tokens.iter().enumerate() {
+        match current {
+            Token::Open => {
+                let is_field = !tables.is_empty() && k >= 2 && token(k - 1) == Some(&Token::Assign) && matches!(token(k - 2), Some(Token::Name(_)));
+                if let Some(Token::Str(first)) = token(k + 1) {
+                    if token(k + 2) == Some(&Token::Concat) {
+                        if let Some((section, _)) = first.split_once('.') {
+                            file.dynamic_sections.insert(section.to_string());
+                        }
+                    } else if is_locale_key(first) {
+                        file.references.push((first.clone(), position_of(source, tokens[k + 1].1)));
+                    }
+                }
+                tables.push(Table { offset: *offset, is_field, ..Default::default() });
+            }
+            Token::Close => {
+                let Some(table) = tables.pop() else { continue };
+                let nested = table.is_field || tables.iter().any(|t| t.is_field);
+                if nested || AMOUNT_FIELDS.iter().any(|f| table.fields.contains(*f)) || !table.fields.contains("name") {
+                    continue;
+                }
+                let Some(kind) = table.strings.get("type") else { continue };
+                // A name computed from variables alone could be anything in the type's sections
+                let (name, dynamic) = match (table.strings.get("name"), table.dynamic_name) {
+                    (Some(name), _) => (name.clone(), false),
+                    (None, prefix) => (prefix.unwrap_or_default(), true),
+                };
+                let named_elsewhere = table.hidden || NAMED_ELSEWHERE_FIELDS.iter().any(|f| table.fields.contains(*f));
+                let position = position_of(source, table.offset);
+                file.prototypes.push(Prototype { kind: kind.clone(), name, dynamic, named_elsewhere, position });
+            }
+            Token::Name(field) if token(k + 1) == Some(&Token::Assign) => {
+                let Some(table) = tables.last_mut() else { continue };
+                table.fields.insert(field.clone());
+                match (token(k + 2), token(k + 3)) {
+                    (Some(Token::Str(value)), Some(Token::Concat)) if field == "name" => table.dynamic_name = Some(value.clone()),
+                    (Some(Token::Str(value)), next) if next != Some(&Token::Concat) => {
+                        table.strings.insert(field.clone(), value.clone());
+                    }
+                    (Some(Token::Name(value)), _) if field == "hidden" && value == "true" => table.hidden = true,
+                    _ => {}
+                }
+            }
+            Token::Name(name) if name == "require" => {
+                let argument = if token(k + 1) == Some(&Token::Punct('(')) { k + 2 } else { k + 1 };
+                if let Some(Token::Str(module)) = token(argument) {
+                    file.requires.push(module.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    file
+}
+
+/// Whether a string looks like a `section.key` locale key rather than a file name or text
+fn is_locale_key(s: &str) -> bool {
+    let Some((section, key)) = s.split_once('.') else { return false };
+    !section.is_empty()
+        && !key.is_empty()
+        && section.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        && !s.contains(|c: char| c.is_whitespace() || c == '/')
+}
+
+/// Split Lua source into tokens with their byte offsets, dropping comments
+fn tokenize(source: &str) -> Vec<(Token, usize)> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < source.len() {
+        let rest = &source[i..];
+        let c = rest.as_bytes()[0];
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if let Some(comment) = rest.strip_prefix("--") {
+            i += 2 + match long_bracket(comment) {
+                Some((_, len)) => len,
+                None => comment.find('\n').unwrap_or(comment.len()),
+            };
+        } else if let Some((content, len)) = long_bracket(rest) {
+            tokens.push((Token::Str(content.to_string()), i));
+            i += len;
+        } else if c == b'"' || c == b'\'' {
+            let (value, len) = quoted(rest);
+            tokens.push((Token::Str(value), i));
+            i += len;
+        } else if c.is_ascii_alphanumeric() || c == b'_' {
+            // Numbers become a single punctuation token; only names matter
+            let len = rest.find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || (c.is_ascii_digit() && ch == '.'))).unwrap_or(rest.len());
+            let word = &rest[..len];
+            tokens.push((if c.is_ascii_digit() { Token::Punct('0') } else { Token::Name(word.to_string()) }, i));
+            i += len;
+        } else if rest.starts_with("...") {
+            tokens.push((Token::Punct('.'), i));
+            i += 3;
+        } else if rest.starts_with("..") {
+            tokens.push((Token::Concat, i));
+            i += 2;
+        } else if ["==", "~=", "<=", ">="].iter().any(|op| rest.starts_with(op)) {
+            tokens.push((Token::Punct('='), i));
+            i += 2;
+        } else {
+            let ch = rest.chars().next().unwrap_or_default();
+            let token = match ch {
+                '=' => Token::Assign,
+                '{' => Token::Open,
+                '}' => Token::Close,
+                _ => Token::Punct(ch),
+            };
+            tokens.push((token, i));
+            i += ch.len_utf8();
+        }
+    }
+    tokens
+}
+
+/// Content and total length of a `[[...]]` or `[==[...]==]` long bracket at the start of `s`
+fn long_bracket(s: &str) -> Option<(&str, usize)> {
+    let level = s.strip_prefix('[')?.bytes().take_while(|b| *b == b'=').count();
+    let open = level + 2;
+    if s.as_bytes().get(open - 1) != Some(&b'[') {
+        return None;
+    }
+    let close = format!("]{}]", "=".repeat(level));
+    Some(match s[open..].find(&close) {
+        Some(end) => (&s[open..open + end], open + end + close.len()),
+        None => (&s[open..], s.len()),
+    })
+}
+
+/// Value and length of the quoted string at the start of `s`, resolving the common escapes
+fn quoted(s: &str) -> (String, usize) {
+    let quote = s.as_bytes()[0] as char;
+    let mut value = String::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, escaped)) => value.push(escaped),
+                None => break,
+            },
+            '\n' => return (value, i),
+            _ if c == quote => return (value, i + 1),
+            _ => value.push(c),
+        }
+    }
+    (value, s.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<Token> {
+        tokenize(source).into_iter().map(|(token, _)| token).collect()
+    }
+
+    #[test]
+    fn dashes_inside_strings_are_not_comments() {
+        let source = "local s = \"a--b\" -- {\"item-name.hidden\"}\nx = 'c--'";
+        assert_eq!(
+            tokens(source),
+            [
+                Token::Name("local".into()),
+                Token::Name("s".into()),
+                Token::Assign,
+                Token::Str("a--b".into()),
+                Token::Name("x".into()),
+                Token::Assign,
+                Token::Str("c--".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_brackets_need_a_matching_level() {
+        let source = "--[==[ {\"item-name.a\"} ]] still comment ]==]\ns = [==[ keeps ]] and -- ]==]";
+        assert_eq!(tokens(source), [Token::Name("s".into()), Token::Assign, Token::Str(" keeps ]] and -- ".into())]);
+    }
+
+    #[test]
+    fn computed_names_keep_their_literal_prefix() {
+        let source = "for tier = 1, 3 do\n  data:extend({{type = \"item\", name = \"foo-\" .. tier, localised_name = {\"item-name.foo\", tier}}})\nend\n-- {\"item-name.commented\"}";
+        let file = scan_lua(source);
+        assert_eq!(file.prototypes.len(), 1);
+        let prototype = &file.prototypes[0];
+        assert_eq!((prototype.kind.as_str(), prototype.name.as_str(), prototype.dynamic), ("item", "foo-", true));
+        assert!(prototype.named_elsewhere);
+        assert_eq!(file.references, [("item-name.foo".to_string(), (2, 73))]);
+    }
+}
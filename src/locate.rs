@@ -0,0 +1,322 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::compat::{BASE_MOD, DLC_MODS};
+use crate::config::Settings;
+use crate::mod_info::Version;
+use crate::platform::{game_root, mods_dir_for_install};
+
+/// How a Factorio installation was found
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum InstallKind {
+    Standalone,
+    Steam,
+    Flatpak,
+    Server,
+}
+
+impl fmt::Display for InstallKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InstallKind::Standalone => "standalone",
+            InstallKind::Steam => "steam",
+            InstallKind::Flatpak => "flatpak",
+            InstallKind::Server => "server",
+        })
+    }
+}
+
+/// A Factorio game folder found on this machine
+pub struct Installation {
+    /// Unique name usable with `--target`, e.g. `steam` or `server-2`
+    pub name: String,
+    pub kind: InstallKind,
+    pub dir: PathBuf,
+    /// Game version from `data/base/info.json`
+    pub version: Option<Version>,
+    pub mods_dir: PathBuf,
+}
+
+impl Installation {
+    /// Whether `selector` names this installation, or is a prefix of its version (`2.0` matches 2.0.28)
+    pub fn matches(&self, selector: &str) -> bool {
+        if self.name == selector {
+            return true;
+        }
+        let Some(version) = self.version else { return false };
+        let version = version.to_string();
+        version == selector || version.starts_with(&format!("{}.", selector))
+    }
+}
+
+/// A folder that might hold a game, before it is checked
+struct Candidate {
+    kind: InstallKind,
+    dir: PathBuf,
+    /// Home directory the game sees when it runs sandboxed
+    sandbox_home: Option<PathBuf>,
+}
+
+/// Probe the usual install locations and return every folder that really holds a game
+pub fn find_installations() -> Vec<Installation> {
+    let mut seen = HashSet::new();
+    let mut found: Vec<Installation> = Vec::new();
+
+    for candidate in candidates() {
+        let Some(base_info) = base_info_path(&candidate.dir) else { continue };
+        let Ok(canonical) = fs::canonicalize(&candidate.dir) else { continue };
+        if !seen.insert(canonical) {
+            continue;
+        }
+
+        let version = read_base_version(&base_info);
+        let Ok(mut mods_dir) = mods_dir_for_install(&candidate.dir) else { continue };
+        if let (Some(sandbox), Some(home)) = (&candidate.sandbox_home, dirs_next::home_dir())
+            && let Ok(rest) = mods_dir.strip_prefix(&home)
+        {
+            mods_dir = sandbox.join(rest);
+        }
+
+        let taken = found.iter().filter(|i| i.kind == candidate.kind).count();
+        let name = if taken == 0 { candidate.kind.to_string() } else { format!("{}-{}", candidate.kind, taken + 1) };
+        found.push(Installation { name, kind: candidate.kind, dir: candidate.dir, version, mods_dir });
+    }
+    found
+}
+
+/// `data/base/info.json` of a game folder; macOS app bundles keep it under `Contents/`
+fn base_info_path(dir: &Path) -> Option<PathBuf> {
+    Some(game_root(dir).join("data/base/info.json")).filter(|path| path.is_file())
+}
+
+/// Version of the game installed in `dir`, if it is a game folder
+pub fn game_version(dir: &Path) -> Option<Version> {
+    read_base_version(&base_info_path(dir)?)
+}
+
+/// Mods the game in `dir` ships in its `data/` folder: always `base`, plus the expansion mods a 2.0+ game
+/// has installed. With an unknown game only `base` is certain.
+pub fn builtin_mods(dir: Option<&Path>, version: Option<Version>) -> Vec<&'static str> {
+    let mut mods = vec![BASE_MOD];
+    let data = dir.and_then(base_info_path).and_then(|info| Some(info.parent()?.parent()?.to_path_buf()));
+    if let (Some(data), Some(version)) = (data, version)
+        && version.major >= 2
+    {
+        mods.extend(DLC_MODS.iter().filter(|name| data.join(name).join("info.json").is_file()));
+    }
+    mods
+}
+
+fn read_base_version(base_info: &Path) -> Option<Version> {
+    let content = fs::read_to_string(base_info).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    json.get("version")?.as_str()?.parse().ok()
+}
+
+fn candidates() -> Vec<Candidate> {
+    let home = dirs_next::home_dir().unwrap_or_default();
+    let mut out = Vec::new();
+    let mut push = |kind, dir: PathBuf| out.push(Candidate { kind, dir, sandbox_home: None });
+
+    #[cfg(target_os = "windows")]
+    {
+        for var in ["ProgramFiles", "ProgramFiles(x86)"] {
+            if let Ok(dir) = std::env::var(var) {
+                push(InstallKind::Standalone, PathBuf::from(dir).join("Factorio"));
+            }
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        push(InstallKind::Standalone, PathBuf::from("/Applications/factorio.app"));
+        push(InstallKind::Standalone, home.join("Applications/factorio.app"));
+    }
+
+    #[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+    {
+        for dir in [home.join("factorio"), home.join("Games/factorio")] {
+            push(InstallKind::Standalone, dir);
+        }
+        // Headless servers are conventionally unpacked into /opt
+        if let Ok(entries) = fs::read_dir("/opt") {
+            let mut dirs: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.file_name().is_some_and(|n| n.to_string_lossy().to_lowercase().starts_with("factorio")))
+                .collect();
+            dirs.sort();
+            for dir in dirs {
+                push(InstallKind::Server, dir);
+            }
+        }
+    }
+
+    for steam_root in steam_roots(&home) {
+        for library in steam_libraries(&steam_root) {
+            let dir = library.join("steamapps/common/Factorio");
+            // Steam on macOS installs the app bundle inside the game folder
+            #[cfg(target_os = "macos")]
+            let dir = dir.join("factorio.app");
+            out.push(Candidate { kind: InstallKind::Steam, dir, sandbox_home: None });
+        }
+    }
+
+    #[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+    {
+        // Steam installed from Flathub runs with its own home under ~/.var/app
+        let sandbox = home.join(".var/app/com.valvesoftware.Steam");
+        for library in steam_libraries(&sandbox.join(".local/share/Steam")) {
+            out.push(Candidate {
+                kind: InstallKind::Flatpak,
+                dir: library.join("steamapps/common/Factorio"),
+                sandbox_home: Some(sandbox.clone()),
+            });
+        }
+    }
+    out
+}
+
+/// Steam client folders holding `steamapps/libraryfolders.vdf`
+fn steam_roots(home: &Path) -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        let _ = home;
+        ["ProgramFiles(x86)", "ProgramFiles"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .map(|dir| PathBuf::from(dir).join("Steam"))
+            .collect()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        vec![home.join("Library/Application Support/Steam")]
+    }
+
+    #[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+    {
+        vec![home.join(".steam/steam"), home.join(".local/share/Steam")]
+    }
+}
+
+/// Every library folder listed in a Steam client's `libraryfolders.vdf`, the client folder included
+fn steam_libraries(steam_root: &Path) -> Vec<PathBuf> {
+    let mut libraries = vec![steam_root.to_path_buf()];
+    if let Ok(content) = fs::read_to_string(steam_root.join("steamapps/libraryfolders.vdf")) {
+        libraries.extend(library_paths(&content));
+    }
+    libraries
+}
+
+/// Library folders in the text of a `libraryfolders.vdf`.
+///
+/// The current format has `"path"  "/games/steam"` inside numbered blocks, next to an `"apps"` block of
+/// appid/size pairs; the legacy format lists `"1"  "D:\\Steam"` directly in the top-level block.
+fn library_paths(vdf: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut depth = 0usize;
+    for line in vdf.lines() {
+        let tokens = quoted_strings(line);
+        if let [key, value] = tokens.as_slice() {
+            let legacy = depth == 1 && !key.is_empty() && key.chars().all(|c| c.is_ascii_digit());
+            if legacy || (depth == 2 && key == "path") {
+                paths.push(PathBuf::from(value.replace("\\\\", "\\")));
+            }
+        }
+        // Braces sit on their own lines, outside any quoted string
+        match line.trim() {
+            "{" => depth += 1,
+            "}" => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    paths
+}
+
+/// The double-quoted tokens of a VDF line
+fn quoted_strings(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars();
+    while chars.by_ref().any(|c| c == '"') {
+        let mut token = String::new();
+        let mut escaped = false;
+        for c in chars.by_ref() {
+            if c == '"' && !escaped {
+                break;
+            }
+            escaped = c == '\\' && !escaped;
+            token.push(c);
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Print every detected installation and the mods directories the current settings resolve to
+pub fn locate_installations(settings: &Settings) -> Result<()> {
+    let installations = find_installations();
+    if installations.is_empty() {
+        println!("⚠️  No Factorio installation found");
+    }
+    for install in &installations {
+        let version = install.version.map_or_else(|| "unknown version".to_string(), |v| v.to_string());
+        println!("🎮 {} ({}) at {}", install.name, version, install.dir.display());
+        println!("    mods: {}", install.mods_dir.display());
+    }
+
+    for target in settings.targets()? {
+        let state = if target.mods_dir.is_dir() { "" } else { " (does not exist yet)" };
+        let game = target.game_version.map_or_else(String::new, |v| format!(", game {}", v));
+        println!("🎯 Target {} → {}{}{}", target.name, target.mods_dir.display(), game, state);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn library_paths_skip_app_sizes() {
+        let vdf = r#""libraryfolders"
+{
+	"0"
+	{
+		"path"		"/home/me/.local/share/Steam"
+		"label"		""
+		"contentid"		"4473946223683427433"
+		"totalsize"		"0"
+		"update_clean_bytes_tobedeleted"		"0"
+		"update_dirty_bytes_tobedeleted"		"0"
+		"apps"
+		{
+			"228980"		"406198036"
+			"427520"		"0"
+		}
+	}
+	"1"
+	{
+		"path"		"D:\\SteamLibrary"
+		"label"		""
+		"contentid"		"8133291436213358932"
+		"totalsize"		"1000186310656"
+		"apps"
+		{
+			"427520"		"3465789952"
+		}
+	}
+}
+"#;
+        assert_eq!(library_paths(vdf), [PathBuf::from("/home/me/.local/share/Steam"), PathBuf::from("D:\\SteamLibrary")]);
+    }
+
+    #[test]
+    fn library_paths_read_legacy_numbered_entries() {
+        let vdf = "\"LibraryFolders\"\n{\n\t\"TimeNextStatsReport\"\t\t\"1561832478\"\n\t\"ContentStatsID\"\t\t\"-5980096405410542190\"\n\t\"1\"\t\t\"D:\\\\Steam\"\n}\n";
+        assert_eq!(library_paths(vdf), [PathBuf::from("D:\\Steam")]);
+    }
+}
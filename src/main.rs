@@ -1,15 +1,46 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use std::path::{Path, PathBuf};
 
+mod build;
+mod bump;
+mod changelog;
+mod check;
+mod compat;
 mod config;
+mod deps;
+mod diagnostics;
+mod git;
+mod installed;
 mod installer;
+mod linker;
+mod locale;
+mod locale_refs;
+mod locate;
 mod mod_info;
+mod mod_list;
+mod mod_settings;
 mod platform;
+mod portal;
+mod property_tree;
+mod publish;
+#[cfg(test)]
+mod test_support;
+mod time;
+mod workspace;
 mod zip_builder;
 
-use config::BuildConfig;
-use installer::install_mods;
+use build::build_mods;
+use bump::{bump_mods, Bump};
+use changelog::{format_changelogs, show_changelogs, NotesFormat};
+use check::check_mods;
+use config::{BuildOptions, DiscoveryConfig, GitFilter, Settings, TargetSelection, WorkspaceConfig};
+use deps::fetch_dependencies;
+use installer::{install_mods, toggle_mods, uninstall_mods, ReplacePolicy};
+use linker::{link_mods, unlink_mods};
+use locate::locate_installations;
+use mod_settings::{dump_settings, reset_settings, set_setting, SettingScope};
+use publish::publish_mods;
 
 #[derive(Parser)]
 #[command(author, version, about = "Factorio mod helper (zip + install)")]
@@ -25,29 +56,494 @@ enum Commands {
         /// Optional path to a mod folder containing info.json. If omitted, installs all detected mods in the repo.
         mod_path: Option<PathBuf>,
 
-        /// Output directory for the built .zip(s) before install (default: build)
-        #[arg(long, default_value = "build")]
-        out_dir: String,
+        /// What to do with other installed versions of the mod.
+        #[arg(long, value_enum, default_value = "remove")]
+        replace: ReplacePolicy,
 
-        /// Optional default thumbnail to use when a submod has none.
+        /// Enable the installed mods and their required dependencies in mod-list.json.
+        #[arg(long)]
+        enable: bool,
+
+        /// Install even if the target game version doesn't match factorio_version or the base dependency, and
+        /// replace unpacked folders and links of the mod that cargo factorio did not create.
+        #[arg(long)]
+        force: bool,
+
+        #[command(flatten)]
+        build: BuildArgs,
+
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+
+    /// Remove a mod (or all detected mods) from your Factorio mods/ folder and mod-list.json
+    Uninstall {
+        /// Optional path to a mod folder containing info.json. If omitted, uninstalls all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        /// Also delete unzipped mod folders that cargo factorio did not create
+        #[arg(long)]
+        force: bool,
+
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+
+    /// Enable a mod (or all detected mods) in mod-list.json
+    Enable {
+        /// Optional path to a mod folder containing info.json. If omitted, enables all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        /// Also enable required dependencies that are installed but disabled.
+        #[arg(long)]
+        with_dependencies: bool,
+
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+
+    /// Disable a mod (or all detected mods) in mod-list.json
+    Disable {
+        /// Optional path to a mod folder containing info.json. If omitted, disables all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+
+    /// Build the .zip of a mod (or all detected mods) without installing it
+    Build {
+        /// Optional path to a mod folder containing info.json. If omitted, builds all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        #[command(flatten)]
+        build: BuildArgs,
+    },
+
+    /// Build a mod (or all detected mods) and upload the release to the Factorio mod portal
+    Publish {
+        /// Optional path to a mod folder containing info.json. If omitted, publishes all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        /// Mod portal to upload to (default: https://mods.factorio.com, or $FACTORIO_PORTAL_URL).
+        #[arg(long, value_name = "URL")]
+        portal_url: Option<String>,
+
+        /// Upload the zip already in the output directory instead of building it again.
+        #[arg(long)]
+        no_build: bool,
+
+        /// Also replace the portal summary and description with the mod's README.md.
+        #[arg(long)]
+        readme: bool,
+
+        #[command(flatten)]
+        build: BuildArgs,
+    },
+
+    /// Symlink a mod's source folder (or all detected mods) into your Factorio mods/ folder for fast iteration
+    Link {
+        /// Optional path to a mod folder containing info.json. If omitted, links all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        #[command(flatten)]
+        link: LinkArgs,
+
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+
+    /// Remove links created by `link`
+    Unlink {
+        /// Optional path to a mod folder containing info.json. If omitted, unlinks all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+
+    /// List the Factorio installations on this machine and the mods folders they use
+    #[command(visible_alias = "doctor")]
+    Locate {
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+
+    /// Manage the dependencies of a mod (or all detected mods)
+    #[command(subcommand)]
+    Deps(DepsCommand),
+
+    /// Inspect or edit mod-settings.dat
+    #[command(name = "settings", subcommand)]
+    ModSettings(SettingsCommand),
+
+    /// Raise the version in info.json and start a new changelog.txt section for it
+    Bump {
+        /// major, minor, patch, or an explicit x.y.z version
+        level: Bump,
+
+        /// Optional path to a mod folder containing info.json. If omitted, bumps all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        /// Changelog entry for the new version, under "Changes:". Repeatable; required unless changelog.txt already starts with that version.
+        #[arg(short, long = "message", value_name = "TEXT")]
+        messages: Vec<String>,
+
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+    },
+
+    /// Work with changelog.txt
+    #[command(subcommand)]
+    Changelog(ChangelogCommand),
+
+    /// Validate info.json, changelog.txt, locale files and the locale keys Lua code uses, for a mod (or all detected mods), without building anything
+    Check {
+        /// Optional path to a mod folder containing info.json. If omitted, checks all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum ChangelogCommand {
+    /// Print one version's changelog entries as release notes
+    Show {
+        /// Version to show (default: the version in info.json). A mod folder given here is taken as the mod path.
+        version: Option<String>,
+
+        /// Optional path to a mod folder containing info.json. If omitted, shows all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        /// Output format.
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: NotesFormat,
+
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+    },
+
+    /// Print changelog.txt rewritten in the exact format the game expects
+    Fmt {
+        /// Optional path to a mod folder containing info.json. If omitted, prints for all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum DepsCommand {
+    /// Download missing required dependencies from the mod portal into the mods folder
+    Fetch {
+        /// Optional path to a mod folder containing info.json. If omitted, fetches for all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        /// Mod portal to use (default: https://mods.factorio.com, or $FACTORIO_PORTAL_URL).
+        #[arg(long, value_name = "URL")]
+        portal_url: Option<String>,
+
+        /// Print which portal is used.
+        #[arg(long)]
+        verbose: bool,
+
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum SettingsCommand {
+    /// Print mod-settings.dat as JSON
+    Dump {
+        /// mod-settings.dat to read (default: the one in your Factorio mods/ folder)
         #[arg(long, value_name = "PATH")]
-        default_thumbnail: Option<PathBuf>,
+        file: Option<PathBuf>,
+
+        #[command(flatten)]
+        target: TargetArgs,
+    },
 
-        /// Print extra information while building.
+    /// Set a setting, keeping the type of its current value
+    Set {
+        /// Setting name, as declared in settings.lua
+        name: String,
+
+        /// New value: true/false, a number, a string, or a JSON object for colors
+        value: String,
+
+        /// Settings stage; required when the setting isn't stored yet
+        #[arg(long, value_enum)]
+        scope: Option<SettingScope>,
+
+        /// mod-settings.dat to edit (default: the one in your Factorio mods/ folder)
+        #[arg(long, value_name = "PATH")]
+        file: Option<PathBuf>,
+
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+
+    /// Forget stored settings of a mod (or all detected mods) so they revert to their defaults
+    Reset {
+        /// Optional path to a mod folder containing info.json. If omitted, resets all detected mods in the repo.
+        mod_path: Option<PathBuf>,
+
+        /// mod-settings.dat to edit (default: the one in your Factorio mods/ folder)
+        #[arg(long, value_name = "PATH")]
+        file: Option<PathBuf>,
+
+        /// Print which settings are matched and why.
         #[arg(long)]
         verbose: bool,
+
+        #[command(flatten)]
+        discovery: DiscoveryArgs,
+
+        #[command(flatten)]
+        target: TargetArgs,
     },
 }
 
+/// Options shared by every command that builds zips
+#[derive(Args)]
+struct BuildArgs {
+    /// Output directory for the built .zip(s) (default: build)
+    #[arg(long, value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+
+    /// Optional default thumbnail to use when a submod has none.
+    #[arg(long, value_name = "PATH")]
+    default_thumbnail: Option<PathBuf>,
+
+    /// Pack files even if git would ignore them.
+    #[arg(long, conflicts_with = "git_tracked_only")]
+    no_gitignore: bool,
+
+    /// Only pack files tracked by git, so a dirty checkout builds the same zip as CI.
+    #[arg(long)]
+    git_tracked_only: bool,
+
+    /// Extra gitignore-style pattern to leave out of the zip. Repeatable.
+    #[arg(long = "ignore", value_name = "PATTERN")]
+    excludes: Vec<String>,
+
+    /// Build byte-identical zips (timestamps from SOURCE_DATE_EPOCH or the last git commit).
+    #[arg(long)]
+    reproducible: bool,
+
+    /// Print extra information while building.
+    #[arg(long)]
+    verbose: bool,
+
+    #[command(flatten)]
+    discovery: DiscoveryArgs,
+}
+
+impl BuildArgs {
+    fn into_settings(self, target: TargetArgs) -> Result<Settings> {
+        let cli = BuildOptions {
+            out_dir: self.out_dir,
+            default_thumbnail: self.default_thumbnail,
+            default_excludes: None,
+            excludes: self.excludes,
+            git_filter: git_filter(self.no_gitignore, self.git_tracked_only),
+            reproducible: self.reproducible.then_some(true),
+        };
+        load_settings(self.verbose, self.discovery, cli, target)
+    }
+}
+
+/// Options for `link`; only the file selection matters, for when it has to copy instead of symlink
+#[derive(Args)]
+struct LinkArgs {
+    /// Copy files even if git would ignore them.
+    #[arg(long, conflicts_with = "git_tracked_only")]
+    no_gitignore: bool,
+
+    /// Only copy files tracked by git.
+    #[arg(long)]
+    git_tracked_only: bool,
+
+    /// Extra gitignore-style pattern to leave out of the copy. Repeatable.
+    #[arg(long = "ignore", value_name = "PATTERN")]
+    excludes: Vec<String>,
+
+    /// Print extra information while linking.
+    #[arg(long)]
+    verbose: bool,
+
+    #[command(flatten)]
+    discovery: DiscoveryArgs,
+}
+
+impl LinkArgs {
+    fn into_settings(self, target: TargetArgs) -> Result<Settings> {
+        let cli = BuildOptions {
+            excludes: self.excludes,
+            git_filter: git_filter(self.no_gitignore, self.git_tracked_only),
+            ..BuildOptions::default()
+        };
+        load_settings(self.verbose, self.discovery, cli, target)
+    }
+}
+
+fn git_filter(no_gitignore: bool, git_tracked_only: bool) -> Option<GitFilter> {
+    if git_tracked_only {
+        Some(GitFilter::TrackedOnly)
+    } else if no_gitignore {
+        Some(GitFilter::Off)
+    } else {
+        None
+    }
+}
+
+/// How mods are found when no mod path is given
+#[derive(Args, Default)]
+struct DiscoveryArgs {
+    /// How many folder levels below the repo root to search for info.json (default: 3).
+    #[arg(long)]
+    max_depth: Option<usize>,
+
+    /// Only use mod folders matching this glob (relative to the repo root). Repeatable.
+    #[arg(long = "include", value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip mod folders matching this glob (relative to the repo root). Repeatable.
+    #[arg(long = "exclude", value_name = "GLOB")]
+    exclude: Vec<String>,
+}
+
+impl DiscoveryArgs {
+    /// Apply the flags that were given on top of the config file's `[discovery]` section
+    fn over(self, base: DiscoveryConfig) -> DiscoveryConfig {
+        DiscoveryConfig {
+            max_depth: self.max_depth.unwrap_or(base.max_depth),
+            include: if self.include.is_empty() { base.include } else { self.include },
+            exclude: if self.exclude.is_empty() { base.exclude } else { self.exclude },
+        }
+    }
+}
+
+/// Which Factorio mods directories to act on
+#[derive(Args, Default)]
+struct TargetArgs {
+    /// Use this mods directory instead of the default one (also: $FACTORIO_MODS_DIR).
+    #[arg(long, value_name = "DIR")]
+    mods_dir: Option<PathBuf>,
+
+    /// Use a target from the `[targets]` config section, or a detected installation by name or version. Repeatable.
+    #[arg(long = "target", value_name = "NAME")]
+    targets: Vec<String>,
+}
+
+impl From<TargetArgs> for TargetSelection {
+    fn from(args: TargetArgs) -> Self {
+        TargetSelection { mods_dir: args.mods_dir, names: args.targets }
+    }
+}
+
+/// Load the workspace config from the current directory and merge it with CLI flags
+fn load_settings(verbose: bool, discovery: DiscoveryArgs, cli: BuildOptions, target: TargetArgs) -> Result<Settings> {
+    let mut workspace = WorkspaceConfig::load(&std::env::current_dir()?)?;
+    let discovery = discovery.over(std::mem::take(&mut workspace.discovery));
+    Ok(Settings::new(verbose, discovery, cli, target.into(), workspace))
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Install { mod_path, out_dir, default_thumbnail, verbose } => {
-            let config = BuildConfig::new(verbose, default_thumbnail);
-            install_mods(mod_path, out_dir, config)?;
+        Commands::Install { mod_path, replace, enable, force, build, target } => {
+            install_mods(mod_path, &build.into_settings(target)?, replace, enable, force)?;
+        }
+        Commands::Uninstall { mod_path, force, discovery, target } => {
+            let settings = load_settings(false, discovery, BuildOptions::default(), target)?;
+            uninstall_mods(mod_path, &settings, force)?;
+        }
+        Commands::Enable { mod_path, with_dependencies, discovery, target } => {
+            let settings = load_settings(false, discovery, BuildOptions::default(), target)?;
+            toggle_mods(mod_path, &settings, true, with_dependencies)?;
+        }
+        Commands::Disable { mod_path, discovery, target } => {
+            let settings = load_settings(false, discovery, BuildOptions::default(), target)?;
+            toggle_mods(mod_path, &settings, false, false)?;
+        }
+        Commands::Build { mod_path, build } => {
+            for built in build_mods(mod_path, &build.into_settings(TargetArgs::default())?)? {
+                println!("{}", built.zip_path.display());
+            }
+        }
+        Commands::Publish { mod_path, portal_url, no_build, readme, build } => {
+            publish_mods(mod_path, &build.into_settings(TargetArgs::default())?, portal_url, no_build, readme)?;
+        }
+        Commands::Link { mod_path, link, target } => {
+            link_mods(mod_path, &link.into_settings(target)?)?;
+        }
+        Commands::Unlink { mod_path, discovery, target } => {
+            let settings = load_settings(false, discovery, BuildOptions::default(), target)?;
+            unlink_mods(mod_path, &settings)?;
+        }
+        Commands::Locate { target } => {
+            let settings = load_settings(false, DiscoveryArgs::default(), BuildOptions::default(), target)?;
+            locate_installations(&settings)?;
+        }
+        Commands::Deps(DepsCommand::Fetch { mod_path, portal_url, verbose, discovery, target }) => {
+            let settings = load_settings(verbose, discovery, BuildOptions::default(), target)?;
+            fetch_dependencies(mod_path, &settings, portal_url)?;
+        }
+        Commands::ModSettings(SettingsCommand::Dump { file, target }) => {
+            let settings = load_settings(false, DiscoveryArgs::default(), BuildOptions::default(), target)?;
+            dump_settings(file, &settings)?;
+        }
+        Commands::ModSettings(SettingsCommand::Set { name, value, scope, file, target }) => {
+            let settings = load_settings(false, DiscoveryArgs::default(), BuildOptions::default(), target)?;
+            set_setting(&name, &value, scope, file, &settings)?;
+        }
+        Commands::ModSettings(SettingsCommand::Reset { mod_path, file, verbose, discovery, target }) => {
+            let settings = load_settings(verbose, discovery, BuildOptions::default(), target)?;
+            reset_settings(mod_path, &settings, file)?;
+        }
+        Commands::Bump { level, mod_path, messages, discovery } => {
+            let settings = load_settings(false, discovery, BuildOptions::default(), TargetArgs::default())?;
+            bump_mods(mod_path, &settings, level, &messages)?;
+        }
+        Commands::Changelog(ChangelogCommand::Show { version, mod_path, format, discovery }) => {
+            let settings = load_settings(false, discovery, BuildOptions::default(), TargetArgs::default())?;
+            // `changelog show ./m` names a mod, not a version
+            let (version, mod_path) = match (version, mod_path) {
+                (Some(path), None) if Path::new(&path).join("info.json").exists() => (None, Some(PathBuf::from(path))),
+                other => other,
+            };
+            show_changelogs(version, mod_path, &settings, format)?;
+        }
+        Commands::Changelog(ChangelogCommand::Fmt { mod_path, discovery }) => {
+            let settings = load_settings(false, discovery, BuildOptions::default(), TargetArgs::default())?;
+            format_changelogs(mod_path, &settings)?;
+        }
+        Commands::Check { mod_path, discovery } => {
+            let settings = load_settings(false, discovery, BuildOptions::default(), TargetArgs::default())?;
+            check_mods(mod_path, &settings)?;
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
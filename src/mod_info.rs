@@ -1,14 +1,280 @@
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use serde::Deserialize;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+use crate::config::DiscoveryConfig;
+use crate::diagnostics::{position_of, Diagnostic};
+
+/// Contents of a mod's info.json, as documented on the Factorio wiki.
+///
+/// Only `name` and `version` are needed to build and install; everything else is
+/// optional here so a sloppy info.json still installs, and `validate_info` reports
+/// what the game or mod portal would reject.
 #[derive(Deserialize)]
+#[allow(dead_code)] // mirrors the whole documented schema, not every field is consumed yet
 pub struct Info {
     pub name: String,
     pub version: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub contact: Option<String>,
+    pub homepage: Option<String>,
+    pub description: Option<String>,
+    /// `major.minor` of the game this mod targets; Factorio assumes 0.12 when missing.
+    pub factorio_version: Option<String>,
+    /// Raw dependency strings; Factorio assumes `["base"]` when missing.
+    pub dependencies: Option<Vec<String>>,
+    pub quality_required: Option<bool>,
+    pub rail_bridges_required: Option<bool>,
+    pub space_travel_required: Option<bool>,
+    pub spoiling_required: Option<bool>,
+    pub freezing_required: Option<bool>,
+    pub segmented_units_required: Option<bool>,
+    pub expansion_shaders_required: Option<bool>,
+}
+
+/// A mod version: three dot-separated numbers, each 0–65535
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl FromStr for Version {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse_version(s, 3).map_err(|e| anyhow!(e))
+    }
+}
+
+/// Parse a dotted version with `min_parts` to three numeric parts; missing parts are zero
+fn parse_version(s: &str, min_parts: usize) -> std::result::Result<Version, String> {
+    let parts: Vec<&str> = s.split('.').collect();
+    if !(min_parts..=3).contains(&parts.len()) {
+        return Err(if min_parts == 3 {
+            format!("version {:?} must have exactly three parts (major.minor.patch)", s)
+        } else {
+            format!("version {:?} must be major.minor or major.minor.patch", s)
+        });
+    }
+
+    let mut numbers = [0u16; 3];
+    for (slot, part) in numbers.iter_mut().zip(&parts) {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("version {:?} has a non-numeric part {:?}", s, part));
+        }
+        *slot = part.parse().map_err(|_| format!("version {:?} has part {} outside 0–65535", s, part))?;
+    }
+    let [major, minor, patch] = numbers;
+    Ok(Version { major, minor, patch })
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// How a dependency affects loading, given by its prefix in info.json
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DependencyKind {
+    /// No prefix: must be present and enabled.
+    Required,
+    /// `?`: loaded before this mod when present.
+    Optional,
+    /// `(?)`: optional, but not shown in the in-game mod manager.
+    HiddenOptional,
+    /// `!`: must not be enabled together with this mod.
+    Incompatible,
+    /// `~`: required, without affecting load order.
+    NoLoadOrder,
+}
+
+impl DependencyKind {
+    /// Prefixes in match order; `(?)` must be tried before `?`
+    const PREFIXES: [(&'static str, DependencyKind); 4] = [
+        ("(?)", DependencyKind::HiddenOptional),
+        ("?", DependencyKind::Optional),
+        ("!", DependencyKind::Incompatible),
+        ("~", DependencyKind::NoLoadOrder),
+    ];
+
+    fn prefix(self) -> &'static str {
+        Self::PREFIXES.iter().find(|(_, kind)| *kind == self).map_or("", |(prefix, _)| prefix)
+    }
+}
+
+/// Comparison operator of a dependency version constraint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VersionOp {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl VersionOp {
+    /// Operators in match order; two-character operators must be tried first
+    const SYMBOLS: [(&'static str, VersionOp); 5] = [
+        ("<=", VersionOp::LessOrEqual),
+        (">=", VersionOp::GreaterOrEqual),
+        ("<", VersionOp::Less),
+        (">", VersionOp::Greater),
+        ("=", VersionOp::Equal),
+    ];
+
+    pub fn symbol(self) -> &'static str {
+        Self::SYMBOLS.iter().find(|(_, op)| *op == self).map_or("=", |(symbol, _)| symbol)
+    }
+
+    /// Whether any `major.minor.x` release satisfies `<op> wanted`
+    pub fn allows_series(self, major: u16, minor: u16, wanted: Version) -> bool {
+        let first = Version { major, minor, patch: 0 };
+        let last = Version { major, minor, patch: u16::MAX };
+        match self {
+            VersionOp::Equal => (wanted.major, wanted.minor) == (major, minor),
+            _ => self.matches(first, wanted) || self.matches(last, wanted),
+        }
+    }
+
+    /// Whether `actual` satisfies `actual <op> wanted`
+    pub fn matches(self, actual: Version, wanted: Version) -> bool {
+        match self {
+            VersionOp::Less => actual < wanted,
+            VersionOp::LessOrEqual => actual <= wanted,
+            VersionOp::Equal => actual == wanted,
+            VersionOp::GreaterOrEqual => actual >= wanted,
+            VersionOp::Greater => actual > wanted,
+        }
+    }
+}
+
+/// One parsed entry of info.json `dependencies`, e.g. `"? base >= 1.1"`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    pub kind: DependencyKind,
+    pub name: String,
+    pub constraint: Option<(VersionOp, Version)>,
+}
+
+impl Dependency {
+    /// Whether the game refuses to load the mod without this dependency
+    pub fn is_required(&self) -> bool {
+        matches!(self.kind, DependencyKind::Required | DependencyKind::NoLoadOrder)
+    }
+}
+
+/// Why a dependency string failed to parse
+#[derive(Debug)]
+pub struct DependencyError {
+    /// 1-based character column within the dependency string
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (column {})", self.message, self.column)
+    }
+}
+
+impl std::error::Error for DependencyError {}
+
+impl FromStr for Dependency {
+    type Err = DependencyError;
+
+    /// Parse `[prefix] name [op version]`, where the version may have two or three parts
+    fn from_str(text: &str) -> std::result::Result<Self, DependencyError> {
+        let error = |offset: usize, message: String| DependencyError { column: text[..offset].chars().count() + 1, message };
+        let skip_ws = |offset: usize| offset + (text[offset..].len() - text[offset..].trim_start().len());
+
+        let mut pos = skip_ws(0);
+        let (kind, prefix_len) = DependencyKind::PREFIXES
+            .iter()
+            .find(|(prefix, _)| text[pos..].starts_with(prefix))
+            .map_or((DependencyKind::Required, 0), |(prefix, kind)| (*kind, prefix.len()));
+        pos = skip_ws(pos + prefix_len);
+
+        let name_end = text[pos..].find(['<', '>', '=']).map_or(text.len(), |i| pos + i);
+        let name = text[pos..name_end].trim_end();
+        if name.is_empty() {
+            return Err(error(pos, "missing mod name".to_string()));
+        }
+        if let Some(c) = name.chars().find(|c| matches!(c, '!' | '?' | '~' | '(' | ')')) {
+            return Err(error(pos + name.find(c).unwrap_or(0), format!("unexpected {:?} in mod name", c)));
+        }
+        if name_end == text.len() {
+            return Ok(Self { kind, name: name.to_string(), constraint: None });
+        }
+
+        let (symbol, op) = VersionOp::SYMBOLS
+            .iter()
+            .find(|(symbol, _)| text[name_end..].starts_with(symbol))
+            .copied()
+            .ok_or_else(|| error(name_end, "expected a comparison operator".to_string()))?;
+        let version_start = skip_ws(name_end + symbol.len());
+        if let Some(c) = text[version_start..].chars().next().filter(|c| matches!(c, '<' | '>' | '=')) {
+            return Err(error(version_start, format!("unexpected {:?} after `{}`", c, symbol)));
+        }
+        let version_text = text[version_start..].trim_end();
+        if version_text.is_empty() {
+            return Err(error(version_start, format!("expected a version after `{}`", symbol)));
+        }
+        let version = parse_version(version_text, 2).map_err(|message| error(version_start, message))?;
+
+        Ok(Self { kind, name: name.to_string(), constraint: Some((op, version)) })
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = self.kind.prefix();
+        let gap = if prefix.is_empty() { "" } else { " " };
+        write!(f, "{}{}{}", prefix, gap, self.name)?;
+        if let Some((op, version)) = self.constraint {
+            write!(f, " {} {}", op.symbol(), version)?;
+        }
+        Ok(())
+    }
 }
 
+/// Longest `name` and `title` the mod portal accepts
+const MAX_NAME_LEN: usize = 100;
+
+/// Space Age feature flags allowed in info.json
+const FEATURE_FLAGS: &[&str] = &[
+    "quality_required",
+    "rail_bridges_required",
+    "space_travel_required",
+    "spoiling_required",
+    "freezing_required",
+    "segmented_units_required",
+    "expansion_shaders_required",
+];
+
+/// Every other documented info.json field
+const KNOWN_FIELDS: &[&str] = &[
+    "name",
+    "version",
+    "title",
+    "author",
+    "contact",
+    "homepage",
+    "description",
+    "factorio_version",
+    "dependencies",
+];
+
 impl Info {
     /// Load mod info from info.json in the given directory
     pub fn load_from_dir(mod_root: &Path) -> Result<Self> {
@@ -18,14 +284,203 @@ impl Info {
         Ok(info)
     }
 
+    /// Parsed dependencies; like the game, a missing `dependencies` field means `["base"]`
+    pub fn parsed_dependencies(&self) -> Result<Vec<Dependency>> {
+        let Some(entries) = &self.dependencies else {
+            return Ok(vec![Dependency { kind: DependencyKind::Required, name: "base".to_string(), constraint: None }]);
+        };
+        entries
+            .iter()
+            .map(|entry| entry.parse().with_context(|| format!("Invalid dependency {:?} in mod `{}`", entry, self.name)))
+            .collect()
+    }
+
+    /// Game `major.minor` the mod is made for; like the game, a missing `factorio_version` means 0.12
+    pub fn factorio_series(&self) -> Result<(u16, u16)> {
+        let Some(text) = &self.factorio_version else { return Ok((0, 12)) };
+        let version = parse_version(text, 2).map_err(|e| anyhow!(e)).with_context(|| format!("Invalid factorio_version in mod `{}`", self.name))?;
+        Ok((version.major, version.minor))
+    }
+
     /// Get the mod's zip name in the format "name_version"
     pub fn zip_name(&self) -> String {
         format!("{}_{}", self.name, self.version)
     }
 }
 
+/// Check info.json in `mod_root` against the documented schema, reporting every violation
+pub fn validate_info(mod_root: &Path) -> Result<Vec<Diagnostic>> {
+    let path = mod_root.join("info.json");
+    let source = fs::read_to_string(&path)?;
+    let json_error = |e: serde_json::Error, prefix: &str| {
+        let message = e.to_string();
+        let message = message.rsplit_once(" at line ").map_or(message.as_str(), |(m, _)| m);
+        vec![Diagnostic::error(&path, (e.line(), e.column()), format!("{}{}", prefix, message))]
+    };
+
+    let value: Value = match serde_json::from_str(&source) {
+        Ok(value) => value,
+        Err(e) => return Ok(json_error(e, "invalid JSON: ")),
+    };
+    let Some(obj) = value.as_object() else {
+        return Ok(vec![Diagnostic::error(&path, (1, 1), "info.json must contain a JSON object")]);
+    };
+    // Missing `name`/`version` and mistyped fields stop here, pointing at the offending value
+    let info: Info = match serde_json::from_str(&source) {
+        Ok(info) => info,
+        Err(e) => return Ok(json_error(e, "")),
+    };
+
+    let mut validator = InfoValidator { path: &path, source: &source, info: &info, diagnostics: Vec::new() };
+    validator.check_name();
+    validator.check_version();
+    validator.check_title();
+    validator.check_author();
+    validator.check_factorio_version();
+    validator.check_dependencies();
+    for key in obj.keys().filter(|key| !KNOWN_FIELDS.contains(&key.as_str()) && !FEATURE_FLAGS.contains(&key.as_str())) {
+        validator.warning(key, format!("unknown field `{}`", key));
+    }
+    Ok(validator.diagnostics)
+}
+
+/// Walks one parsed info.json, pointing each diagnostic at the offending key
+struct InfoValidator<'a> {
+    path: &'a Path,
+    source: &'a str,
+    info: &'a Info,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> InfoValidator<'a> {
+    fn error(&mut self, key: &str, message: impl Into<String>) {
+        let pos = self.key_position(key);
+        self.diagnostics.push(Diagnostic::error(self.path, pos, message));
+    }
+
+    fn warning(&mut self, key: &str, message: impl Into<String>) {
+        let pos = self.key_position(key);
+        self.diagnostics.push(Diagnostic::warning(self.path, pos, message));
+    }
+
+    /// Byte offset of `"key":` in the source
+    fn key_offset(&self, key: &str) -> Option<usize> {
+        let needle = format!("\"{}\"", key);
+        self.source
+            .match_indices(&needle)
+            .find(|(i, _)| self.source[i + needle.len()..].trim_start().starts_with(':'))
+            .map(|(i, _)| i)
+    }
+
+    /// Position of `"key":` in the source, or the start of the file for missing keys
+    fn key_position(&self, key: &str) -> (usize, usize) {
+        self.key_offset(key).map_or((1, 1), |i| position_of(self.source, i))
+    }
+
+    /// Position of a string literal inside the value of `key`, falling back to the key itself
+    fn literal_position(&self, key: &str, literal: &str) -> (usize, usize) {
+        let (Ok(needle), Some(key_offset)) = (serde_json::to_string(literal), self.key_offset(key)) else {
+            return self.key_position(key);
+        };
+        self.source[key_offset..]
+            .find(&needle)
+            .map_or_else(|| self.key_position(key), |i| position_of(self.source, key_offset + i))
+    }
+
+    /// The game loads any name, but the mod portal only accepts letters, digits, '-' and '_'
+    fn check_name(&mut self) {
+        let name = &self.info.name;
+        if name.is_empty() {
+            self.error("name", "`name` must not be empty");
+        } else if name.chars().count() > MAX_NAME_LEN {
+            self.error("name", format!("`name` is longer than {} characters", MAX_NAME_LEN));
+        } else if let Some(c) = name.chars().find(|c| !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_')) {
+            self.warning("name", format!("`name` contains {:?}; the mod portal only accepts letters, digits, '-' and '_'", c));
+        }
+    }
+
+    fn check_version(&mut self) {
+        if let Err(e) = self.info.version.parse::<Version>() {
+            self.error("version", e.to_string());
+        }
+    }
+
+    fn check_title(&mut self) {
+        match &self.info.title {
+            None => self.error("title", "missing required field `title`"),
+            Some(title) if title.trim().is_empty() => self.error("title", "`title` must not be empty"),
+            Some(title) if title.chars().count() > MAX_NAME_LEN => {
+                self.error("title", format!("`title` is longer than {} characters", MAX_NAME_LEN))
+            }
+            Some(_) => {}
+        }
+    }
+
+    fn check_author(&mut self) {
+        match &self.info.author {
+            None => self.error("author", "missing required field `author`"),
+            Some(author) if author.trim().is_empty() => self.error("author", "`author` must not be empty"),
+            Some(_) => {}
+        }
+    }
+
+    fn check_factorio_version(&mut self) {
+        let Some(version) = &self.info.factorio_version else {
+            self.warning("factorio_version", "missing `factorio_version`; Factorio will assume 0.12 and refuse to load the mod");
+            return;
+        };
+        let valid = version
+            .split_once('.')
+            .is_some_and(|(major, minor)| [major, minor].iter().all(|p| p.parse::<u16>().is_ok() && !p.starts_with('+')));
+        if !valid {
+            self.error("factorio_version", format!("`factorio_version` {:?} must be in major.minor form, e.g. \"2.0\"", version));
+        }
+    }
+
+    fn check_dependencies(&mut self) {
+        let info = self.info;
+        let Some(entries) = &info.dependencies else { return };
+        let factorio_version = info.factorio_version.as_deref().and_then(|v| parse_version(v, 2).ok());
+
+        let mut seen = Vec::new();
+        for text in entries {
+            let (line, column) = self.literal_position("dependencies", text);
+            let dependency = match text.parse::<Dependency>() {
+                Ok(dependency) => dependency,
+                Err(e) => {
+                    // Point inside the string literal, just past its opening quote
+                    let pos = (line, column + e.column);
+                    self.diagnostics.push(Diagnostic::error(self.path, pos, format!("dependency {:?}: {}", text, e.message)));
+                    continue;
+                }
+            };
+
+            let problem = if dependency.name == info.name {
+                Some("a mod cannot depend on itself".to_string())
+            } else if seen.contains(&dependency.name) {
+                Some(format!("`{}` is listed more than once", dependency.name))
+            } else if dependency.name == "base" && dependency.kind == DependencyKind::Incompatible {
+                Some("a mod cannot be incompatible with `base`".to_string())
+            } else if let (true, Some((op, wanted)), Some(game)) = (dependency.name == "base", dependency.constraint, factorio_version)
+                && !op.allows_series(game.major, game.minor, wanted)
+            {
+                Some(format!("`base {} {}` excludes factorio_version {}.{}", op.symbol(), wanted, game.major, game.minor))
+            } else {
+                None
+            };
+            if let Some(message) = problem {
+                self.diagnostics.push(Diagnostic::error(self.path, (line, column), format!("dependency {:?}: {}", text, message)));
+            }
+            seen.push(dependency.name);
+        }
+    }
+}
+
+/// Directories never searched for mods
+const SKIPPED_DIRS: &[&str] = &["build", "target", "node_modules"];
+
 /// Resolve mod paths based on input
-pub fn resolve_mod_paths(mod_path: Option<PathBuf>, cwd: &Path) -> Result<Vec<PathBuf>> {
+pub fn resolve_mod_paths(mod_path: Option<PathBuf>, cwd: &Path, discovery: &DiscoveryConfig) -> Result<Vec<PathBuf>> {
     if let Some(p) = mod_path {
         let p = if p.is_absolute() { p } else { cwd.join(p) };
         if !p.join("info.json").exists() {
@@ -33,26 +488,160 @@ pub fn resolve_mod_paths(mod_path: Option<PathBuf>, cwd: &Path) -> Result<Vec<Pa
         }
         Ok(vec![p])
     } else {
-        detect_all_mod_roots(cwd)
+        detect_all_mod_roots(cwd, discovery)
     }
 }
 
-/// Detect mods: include repo root if it has info.json, plus each direct child dir with info.json.
-pub fn detect_all_mod_roots(root: &Path) -> Result<Vec<PathBuf>> {
+/// Detect mods: every folder with an info.json up to `max_depth` levels below the root,
+/// skipping hidden folders, build outputs and anything filtered out by the include/exclude globs.
+/// Two mods declaring the same `name` are an error.
+pub fn detect_all_mod_roots(root: &Path, discovery: &DiscoveryConfig) -> Result<Vec<PathBuf>> {
+    let include = build_globset(&discovery.include)?;
+    let exclude = build_globset(&discovery.exclude)?;
+
+    let walker = WalkBuilder::new(root)
+        .standard_filters(false)
+        .follow_links(false)
+        .max_depth(Some(discovery.max_depth))
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0 || !(name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()))
+        })
+        .build();
+
     let mut mods = Vec::new();
-    
-    // Check if root itself is a mod
-    if root.join("info.json").exists() {
-        mods.push(root.to_path_buf());
-    }
-    
-    // Check direct children
-    for entry in fs::read_dir(root)? {
-        let path = entry?.path();
-        if path.is_dir() && path.join("info.json").exists() {
-            mods.push(path);
+    for entry in walker {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_some_and(|t| t.is_dir()) || !path.join("info.json").exists() {
+            continue;
         }
+
+        let rel = path.strip_prefix(root).unwrap_or(path);
+        let rel = if rel.as_os_str().is_empty() { Path::new(".") } else { rel };
+        if include.as_ref().is_some_and(|g| !g.is_match(rel)) || exclude.as_ref().is_some_and(|g| g.is_match(rel)) {
+            continue;
+        }
+        mods.push(path.to_path_buf());
     }
 
+    ensure_unique_names(&mods)?;
     Ok(mods)
-}
\ No newline at end of file
+}
+
+/// Compile globs into one matcher, or `None` when there are none
+fn build_globset(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // `*` stays within one path segment, like in .gitignore; `**` crosses folders
+        let glob = GlobBuilder::new(pattern).literal_separator(true).build();
+        builder.add(glob.with_context(|| format!("Invalid glob {:?}", pattern))?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+/// Fail when two discovered mods share a `name`; unreadable info.json files are left for `check`
+fn ensure_unique_names(mods: &[PathBuf]) -> Result<()> {
+    let mut seen: HashMap<String, &Path> = HashMap::new();
+    for root in mods {
+        let Ok(info) = Info::load_from_dir(root) else { continue };
+        if let Some(other) = seen.insert(info.name.clone(), root) {
+            bail!("Mod `{}` is defined twice: {} and {}", info.name, other.display(), root.display());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{write_mod, TempDir};
+
+    #[test]
+    fn dependency_parses_prefix_name_and_constraint() {
+        let dependency: Dependency = "(?) a >= 1.2".parse().unwrap();
+        assert_eq!(dependency.kind, DependencyKind::HiddenOptional);
+        assert_eq!(dependency.name, "a");
+        assert_eq!(dependency.constraint, Some((VersionOp::GreaterOrEqual, Version { major: 1, minor: 2, patch: 0 })));
+        assert_eq!(dependency.to_string(), "(?) a >= 1.2.0");
+
+        let spaced: Dependency = "?  my mod<2.0.1".parse().unwrap();
+        assert_eq!((spaced.kind, spaced.name.as_str()), (DependencyKind::Optional, "my mod"));
+        assert_eq!(spaced.constraint, Some((VersionOp::Less, Version { major: 2, minor: 0, patch: 1 })));
+    }
+
+    #[test]
+    fn dependency_errors_point_at_the_character_column() {
+        let error = |text: &str| {
+            let error = text.parse::<Dependency>().unwrap_err();
+            (error.column, error.message)
+        };
+        assert_eq!(error("?"), (2, "missing mod name".to_string()));
+        assert_eq!(error("a!b"), (2, "unexpected '!' in mod name".to_string()));
+        assert_eq!(error("base >= "), (9, "expected a version after `>=`".to_string()));
+        assert_eq!(error("é >> 1.0"), (4, "unexpected '>' after `>`".to_string()));
+    }
+
+    /// A fresh temp dir holding a minimal mod in each of `folders` (relative path, mod name)
+    fn workspace(test: &str, folders: &[(&str, &str)]) -> TempDir {
+        let root = TempDir::new(test);
+        for (folder, name) in folders {
+            write_mod(&root.join(folder), name, &[]);
+        }
+        root
+    }
+
+    #[test]
+    fn discovery_star_stays_within_one_folder() {
+        let root = workspace("discovery-globs", &[("mods/a", "a"), ("mods/nested/b", "b"), ("other/c", "c")]);
+        let discovery = |include: &[&str]| DiscoveryConfig {
+            include: include.iter().map(|p| p.to_string()).collect(),
+            ..DiscoveryConfig::default()
+        };
+        let found = |include: &[&str]| -> Vec<PathBuf> {
+            let roots = detect_all_mod_roots(&root, &discovery(include)).unwrap();
+            roots.iter().map(|r| r.strip_prefix(&root).unwrap().to_path_buf()).collect()
+        };
+        assert_eq!(found(&["mods/*"]), [Path::new("mods/a")]);
+        assert_eq!(found(&["mods/**"]), [Path::new("mods/a"), Path::new("mods/nested/b")]);
+    }
+
+    /// Diagnostics for an info.json with the given source, as (line, column, is_error, message)
+    fn info_diagnostics(test: &str, source: &str) -> Vec<(usize, usize, bool, String)> {
+        let root = TempDir::new(test);
+        fs::write(root.join("info.json"), source).unwrap();
+        let diagnostics = validate_info(&root).unwrap();
+        diagnostics.into_iter().map(|d| (d.line, d.column, d.is_error(), d.message)).collect()
+    }
+
+    #[test]
+    fn portal_unfriendly_name_is_only_a_warning() {
+        let source = "{\n  \"name\": \"Squeak Through\",\n  \"version\": \"1.0.0\",\n  \"title\": \"Squeak\",\n  \"author\": \"a\",\n  \"factorio_version\": \"2.0\"\n}";
+        let diagnostics = info_diagnostics("info-name", source);
+        assert_eq!(diagnostics.len(), 1);
+        let (line, column, is_error, message) = &diagnostics[0];
+        assert_eq!((*line, *column, *is_error), (2, 3, false));
+        assert!(message.starts_with("`name` contains ' '"), "{}", message);
+    }
+
+    #[test]
+    fn typed_fields_report_missing_and_mistyped_values() {
+        let missing = info_diagnostics("info-missing", r#"{"name": "m", "version": "1.0", "factorio_version": "2.0", "title": " "}"#);
+        let messages: Vec<_> = missing.iter().filter(|d| d.2).map(|d| d.3.as_str()).collect();
+        assert_eq!(messages, ["version \"1.0\" must have exactly three parts (major.minor.patch)", "`title` must not be empty", "missing required field `author`"]);
+
+        let mistyped = info_diagnostics("info-mistyped", "{\n  \"name\": \"m\",\n  \"version\": \"1.0.0\",\n  \"quality_required\": \"yes\"\n}");
+        assert_eq!(mistyped, [(4, 27, true, "invalid type: string \"yes\", expected a boolean".to_string())]);
+    }
+
+    #[test]
+    fn discovery_rejects_duplicate_names() {
+        let root = workspace("discovery-duplicates", &[("a", "same"), ("b", "same")]);
+        let error = detect_all_mod_roots(&root, &DiscoveryConfig::default()).unwrap_err().to_string();
+        assert!(error.starts_with("Mod `same` is defined twice"), "{}", error);
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File in the mods directory where Factorio records which mods are enabled
+pub const MOD_LIST_FILE: &str = "mod-list.json";
+
+/// One `mods` entry of mod-list.json
+#[derive(Serialize, Deserialize)]
+pub struct ModListEntry {
+    pub name: String,
+    pub enabled: bool,
+    /// Fields this tool doesn't know about (e.g. a pinned `version`), written back untouched
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ModListFile {
+    mods: Vec<ModListEntry>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+/// Typed view of `mods/mod-list.json` that round-trips unknown fields, key order and indentation
+pub struct ModList {
+    path: PathBuf,
+    file: ModListFile,
+    indent: String,
+}
+
+impl ModList {
+    /// Read mod-list.json from `mods_dir`; `None` when the game hasn't written one yet
+    pub fn load(mods_dir: &Path) -> Result<Option<Self>> {
+        let path = mods_dir.join(MOD_LIST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)?;
+        let file = serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?;
+        Ok(Some(Self { indent: detect_indent(&content), path, file }))
+    }
+
+    /// Read mod-list.json, or start a fresh one listing only `base` like the game would
+    pub fn load_or_new(mods_dir: &Path) -> Result<Self> {
+        if let Some(list) = Self::load(mods_dir)? {
+            return Ok(list);
+        }
+        let base = ModListEntry { name: "base".to_string(), enabled: true, extra: Map::new() };
+        Ok(Self {
+            path: mods_dir.join(MOD_LIST_FILE),
+            file: ModListFile { mods: vec![base], extra: Map::new() },
+            indent: "  ".to_string(),
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ModListEntry> {
+        self.file.mods.iter().find(|entry| entry.name == name)
+    }
+
+    /// Set the enabled flag of `name`, adding an entry if needed; returns whether anything changed
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.file.mods.iter_mut().find(|entry| entry.name == name) {
+            Some(entry) if entry.enabled == enabled => false,
+            Some(entry) => {
+                entry.enabled = enabled;
+                true
+            }
+            None => {
+                self.file.mods.push(ModListEntry { name: name.to_string(), enabled, extra: Map::new() });
+                true
+            }
+        }
+    }
+
+    /// Drop the entry for `name`, returning whether there was one
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.file.mods.len();
+        self.file.mods.retain(|entry| entry.name != name);
+        self.file.mods.len() != before
+    }
+
+    /// Write the file back with its original indentation
+    pub fn save(&self) -> Result<()> {
+        let mut out = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(self.indent.as_bytes());
+        let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+        self.file.serialize(&mut serializer)?;
+        out.push(b'\n');
+        fs::write(&self.path, out).with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+/// Leading whitespace of the first indented line, defaulting to two spaces
+fn detect_indent(content: &str) -> String {
+    content
+        .lines()
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .find(|indent| !indent.is_empty())
+        .unwrap_or("  ")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn round_trips_unknown_fields_and_indentation() {
+        let dir = TempDir::new("mod-list");
+        let source = "{\n\t\"mods\": [\n\t\t{\n\t\t\t\"name\": \"base\",\n\t\t\t\"enabled\": true\n\t\t},\n\t\t{\n\t\t\t\"name\": \"a\",\n\t\t\t\"enabled\": false,\n\t\t\t\"version\": \"1.0.0\"\n\t\t}\n\t],\n\t\"note\": 1\n}\n";
+        fs::write(dir.join(MOD_LIST_FILE), source).unwrap();
+
+        let mut list = ModList::load(&dir).unwrap().unwrap();
+        assert!(list.set_enabled("a", true));
+        assert!(!list.set_enabled("a", true));
+        list.save().unwrap();
+
+        assert_eq!(fs::read_to_string(dir.join(MOD_LIST_FILE)).unwrap(), source.replace("false", "true"));
+    }
+
+    #[test]
+    fn new_list_enables_base_and_appends_entries() {
+        let dir = TempDir::new("mod-list-new");
+        assert!(ModList::load(&dir).unwrap().is_none());
+
+        let mut list = ModList::load_or_new(&dir).unwrap();
+        assert!(list.set_enabled("a", false));
+        assert!(list.remove("a"));
+        assert!(!list.remove("a"));
+        assert!(list.set_enabled("b", true));
+        list.save().unwrap();
+
+        let saved: Value = serde_json::from_str(&fs::read_to_string(dir.join(MOD_LIST_FILE)).unwrap()).unwrap();
+        assert_eq!(saved, serde_json::json!({"mods": [{"name": "base", "enabled": true}, {"name": "b", "enabled": true}]}));
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Settings;
+use crate::mod_info::Version;
+use crate::property_tree::{PropertyTree, Reader};
+use crate::workspace::select_mods;
+
+/// Binary file in the mods directory holding every mod setting value
+pub const MOD_SETTINGS_FILE: &str = "mod-settings.dat";
+
+/// Version stamped into a new mod-settings.dat when the target's game version is unknown
+const FALLBACK_GAME_VERSION: Version = Version { major: 2, minor: 0, patch: 0 };
+
+/// Settings stages Factorio stores separately
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SettingScope {
+    Startup,
+    RuntimeGlobal,
+    RuntimePerUser,
+}
+
+impl SettingScope {
+    const ALL: [SettingScope; 3] = [SettingScope::Startup, SettingScope::RuntimeGlobal, SettingScope::RuntimePerUser];
+
+    /// Top-level dictionary key in mod-settings.dat
+    fn key(self) -> &'static str {
+        match self {
+            SettingScope::Startup => "startup",
+            SettingScope::RuntimeGlobal => "runtime-global",
+            SettingScope::RuntimePerUser => "runtime-per-user",
+        }
+    }
+}
+
+/// mod-settings.dat: the game version that wrote it, then one property tree
+pub struct ModSettingsFile {
+    /// main, major, minor and developer version numbers
+    pub version: [u16; 4],
+    pub tree: PropertyTree,
+}
+
+impl ModSettingsFile {
+    /// A file with no stored values yet, as the game would write it on first launch
+    pub fn empty(game_version: Version) -> Self {
+        let scopes = SettingScope::ALL.iter().map(|s| (s.key().to_string(), PropertyTree::Dictionary(Vec::new()))).collect();
+        Self { version: [game_version.major, game_version.minor, game_version.patch, 0], tree: PropertyTree::Dictionary(scopes) }
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let version = [reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?];
+        let _reserved = reader.u8()?;
+        let tree = PropertyTree::read(&mut reader)?;
+        if !reader.is_at_end() {
+            bail!("trailing data after the settings tree");
+        }
+        Ok(Self { version, tree })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for part in self.version {
+            out.extend_from_slice(&part.to_le_bytes());
+        }
+        out.push(0);
+        self.tree.write(&mut out);
+        out
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&bytes).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_bytes()).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn scope_mut(&mut self, scope: SettingScope) -> Result<&mut PropertyTree> {
+        if self.tree.get(scope.key()).is_none() {
+            self.tree.insert(scope.key(), PropertyTree::Dictionary(Vec::new()))?;
+        }
+        self.tree.get_mut(scope.key()).context("settings tree is not a dictionary")
+    }
+}
+
+/// Resolve the mod-settings.dat to operate on: an explicit file, or the one in the target mods directory
+fn settings_path(file: Option<PathBuf>, settings: &Settings) -> Result<PathBuf> {
+    match file {
+        Some(file) => Ok(file),
+        None => Ok(settings.single_target()?.mods_dir.join(MOD_SETTINGS_FILE)),
+    }
+}
+
+/// Print mod-settings.dat as JSON
+pub fn dump_settings(file: Option<PathBuf>, settings: &Settings) -> Result<()> {
+    let settings = ModSettingsFile::load(&settings_path(file, settings)?)?;
+    let [main, major, minor, developer] = settings.version;
+    let json = serde_json::json!({
+        "version": format!("{}.{}.{}.{}", main, major, minor, developer),
+        "settings": settings.tree.to_json(),
+    });
+    println!("{}", serde_json::to_string_pretty(&json)?);
+    Ok(())
+}
+
+/// Set one setting's value, keeping the stored type of an existing value.
+/// A missing mod-settings.dat starts out empty, stamped with the target's game version.
+pub fn set_setting(name: &str, raw_value: &str, scope: Option<SettingScope>, file: Option<PathBuf>, settings: &Settings) -> Result<()> {
+    let path = settings_path(file, settings)?;
+    let mut settings = if path.exists() {
+        ModSettingsFile::load(&path)?
+    } else {
+        let game_version = settings.single_target().ok().and_then(|t| t.game_version).unwrap_or(FALLBACK_GAME_VERSION);
+        ModSettingsFile::empty(game_version)
+    };
+
+    let scope = match scope {
+        Some(scope) => scope,
+        None => SettingScope::ALL
+            .into_iter()
+            .find(|s| settings.tree.get(s.key()).and_then(|d| d.get(name)).is_some())
+            .with_context(|| format!("Unknown setting `{}`; pass --scope to create it", name))?,
+    };
+
+    let dict = settings.scope_mut(scope)?;
+    let existing = dict.get(name).and_then(|entry| entry.get("value"));
+    let value = convert_value(raw_value, existing).with_context(|| format!("Invalid value for `{}`", name))?;
+
+    dict.insert(name, PropertyTree::Dictionary(vec![("value".to_string(), value)]))?;
+    settings.save(&path)?;
+    println!("⚙️  Set {} `{}` = {}", scope.key(), name, raw_value);
+    Ok(())
+}
+
+/// Parse a command-line value, shaped like `existing` when there is one
+fn convert_value(raw: &str, existing: Option<&PropertyTree>) -> Result<PropertyTree> {
+    let json: Option<Value> = serde_json::from_str(raw).ok();
+    Ok(match (existing, json) {
+        (Some(PropertyTree::String(_)), _) | (None, None) => PropertyTree::String(raw.to_string()),
+        (Some(PropertyTree::Bool(_)), Some(Value::Bool(b))) => PropertyTree::Bool(b),
+        (Some(PropertyTree::Number(_)), Some(Value::Number(n))) => PropertyTree::Number(n.as_f64().unwrap_or_default()),
+        (Some(PropertyTree::SignedInteger(_)), Some(Value::Number(n))) if n.is_i64() => {
+            PropertyTree::SignedInteger(n.as_i64().unwrap_or_default())
+        }
+        (Some(PropertyTree::UnsignedInteger(_)), Some(Value::Number(n))) if n.is_u64() => {
+            PropertyTree::UnsignedInteger(n.as_u64().unwrap_or_default())
+        }
+        (Some(PropertyTree::Dictionary(_)), Some(json @ Value::Object(_))) | (None, Some(json)) => PropertyTree::from_json(&json),
+        (Some(existing), _) => bail!("expected {} like the current value {}", describe(existing), existing.to_json()),
+    })
+}
+
+fn describe(tree: &PropertyTree) -> &'static str {
+    match tree {
+        PropertyTree::Bool(_) => "true or false",
+        PropertyTree::Number(_) => "a number",
+        PropertyTree::SignedInteger(_) | PropertyTree::UnsignedInteger(_) => "an integer",
+        PropertyTree::Dictionary(_) => "a JSON object",
+        _ => "a value",
+    }
+}
+
+/// Remove the selected mods' settings from every scope so the game falls back to their defaults
+pub fn reset_settings(mod_path: Option<PathBuf>, settings: &Settings, file: Option<PathBuf>) -> Result<()> {
+    let path = settings_path(file, settings)?;
+    let mut mod_settings = ModSettingsFile::load(&path)?;
+
+    let mut changed = false;
+    for m in select_mods(mod_path, settings)? {
+        let owned = declared_setting_names(&m.root)?;
+        let prefix = format!("{}-", m.info.name);
+        if owned.is_empty() {
+            settings.log(&format!("🔍 No settings*.lua names found in {}, matching prefix `{}`", m.root.display(), prefix));
+        }
+
+        for scope in SettingScope::ALL {
+            let Some(dict) = mod_settings.tree.get_mut(scope.key()) else { continue };
+            let removed = dict.remove_where(|key| {
+                if owned.is_empty() { key.starts_with(&prefix) } else { owned.iter().any(|o| o == key) }
+            });
+            for key in removed {
+                println!("♻️  Reset {} `{}` ({})", scope.key(), key, m.info.name);
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        mod_settings.save(&path)?;
+    } else {
+        println!("ℹ️  No stored settings to reset");
+    }
+    Ok(())
+}
+
+/// Setting names declared as `name = "..."` in the mod's settings stage files
+fn declared_setting_names(mod_root: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for file in ["settings.lua", "settings-updates.lua", "settings-final-fixes.lua"] {
+        let path = mod_root.join(file);
+        if path.exists() {
+            names.extend(name_assignments(&fs::read_to_string(&path)?));
+        }
+    }
+    Ok(names)
+}
+
+/// String literals assigned to a `name` field, e.g. `name = "my-mod-speed"`
+fn name_assignments(source: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for (i, _) in source.match_indices("name") {
+        let standalone = source[..i].chars().next_back().is_none_or(|c| !(c.is_alphanumeric() || c == '_' || c == '.'));
+        let rest = source[i + 4..].trim_start();
+        let Some(rest) = rest.strip_prefix('=').map(str::trim_start) else { continue };
+        let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else { continue };
+        if let (true, Some(end)) = (standalone, rest[1..].find(quote)) {
+            names.push(rest[1..1 + end].to_string());
+        }
+    }
+    names
+}
@@ -1,24 +1,169 @@
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-/// Get the platform-specific Factorio mods directory
-pub fn factorio_mods_dir() -> Result<PathBuf> {
+use crate::mod_info::Version;
+
+/// Environment variable that overrides the default mods directory
+pub const MODS_DIR_ENV: &str = "FACTORIO_MODS_DIR";
+
+/// A mods directory to deploy into, with the name it was selected by
+pub struct InstallTarget {
+    pub name: String,
+    pub mods_dir: PathBuf,
+    /// Version of the game that loads this mods directory, when it could be found
+    pub game_version: Option<Version>,
+    /// Folder of that game, when it could be found
+    pub game_dir: Option<PathBuf>,
+}
+
+/// Get the platform-specific Factorio user data directory (the parent of mods/)
+pub fn factorio_user_data_dir() -> Result<PathBuf> {
     let home = dirs_next::home_dir().context("No home directory found")?;
     
     #[cfg(target_os = "windows")]
     {
         let appdata = std::env::var("APPDATA")
             .unwrap_or_else(|_| home.join("AppData\\Roaming").to_string_lossy().to_string());
-        Ok(PathBuf::from(appdata).join("Factorio\\mods"))
+        Ok(PathBuf::from(appdata).join("Factorio"))
     }
     
     #[cfg(target_os = "macos")]
     {
-        Ok(home.join("Library/Application Support/factorio/mods"))
+        Ok(home.join("Library/Application Support/factorio"))
     }
     
     #[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
     {
-        Ok(home.join(".factorio/mods"))
+        Ok(home.join(".factorio"))
+    }
+}
+
+/// Get the platform-specific Factorio mods directory
+pub fn factorio_mods_dir() -> Result<PathBuf> {
+    Ok(factorio_user_data_dir()?.join("mods"))
+}
+
+/// Mods directory of the game installed at `install_dir`, following its config-path.cfg and config.ini.
+///
+/// Installs that use the system data directories (the default for Steam and installers) write
+/// to the user data directory; portable and headless installs usually write next to the game.
+pub fn mods_dir_for_install(install_dir: &Path) -> Result<PathBuf> {
+    let install_dir = &game_root(install_dir);
+    let exe_dir = executable_dir(install_dir);
+    let cfg_path = install_dir.join("config-path.cfg");
+    if !cfg_path.exists() {
+        return Ok(install_dir.join("mods"));
+    }
+
+    let cfg = fs::read_to_string(&cfg_path).with_context(|| format!("Failed to read {}", cfg_path.display()))?;
+    let cfg_value = |key: &str| {
+        cfg.lines()
+            .filter_map(|line| line.split_once('='))
+            .find(|(k, _)| k.trim() == key)
+            .map(|(_, v)| v.trim().to_string())
+    };
+    if cfg_value("use-system-read-write-data-directories").as_deref() == Some("true") {
+        return factorio_mods_dir();
+    }
+
+    let config_dir = cfg_value("config-path").map_or_else(|| install_dir.join("config"), |p| expand_path(&p, &exe_dir));
+    let write_data = read_ini_value(&config_dir.join("config.ini"), "path", "write-data")?
+        .map_or_else(|| Ok(install_dir.to_path_buf()), |p| resolve_write_data(&p, &exe_dir))?;
+    Ok(write_data.join("mods"))
+}
+
+/// Folder holding config-path.cfg and data/: the install dir itself, or `Contents/` of a macOS app bundle
+pub fn game_root(install_dir: &Path) -> PathBuf {
+    if install_dir.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("app")) {
+        install_dir.join("Contents")
+    } else {
+        install_dir.to_path_buf()
+    }
+}
+
+/// Folder holding the game binary, which `__PATH__executable__` refers to
+fn executable_dir(game_root: &Path) -> PathBuf {
+    let mac = game_root.join("MacOS");
+    if mac.exists() { mac } else { game_root.join("bin").join("x64") }
+}
+
+fn resolve_write_data(value: &str, exe_dir: &Path) -> Result<PathBuf> {
+    if value.starts_with("__PATH__system-write-data__") {
+        let rest = value.trim_start_matches("__PATH__system-write-data__").trim_start_matches(['/', '\\']);
+        return Ok(factorio_user_data_dir()?.join(rest));
+    }
+    Ok(expand_path(value, exe_dir))
+}
+
+/// Substitute `__PATH__executable__` and normalize `..` segments
+fn expand_path(value: &str, exe_dir: &Path) -> PathBuf {
+    let raw = match value.strip_prefix("__PATH__executable__") {
+        Some(rest) => exe_dir.join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(value),
+    };
+    let mut normalized = PathBuf::new();
+    for component in raw.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Read `key` from `[section]` of an INI file; `None` when the file or key is missing
+fn read_ini_value(path: &Path, section: &str, key: &str) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut current = "";
+    for line in content.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = name.trim();
+        } else if current == section
+            && let Some((k, v)) = line.split_once('=')
+            && k.trim() == key
+        {
+            return Ok(Some(v.trim().to_string()));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn install_without_config_path_uses_its_own_mods_folder() {
+        let dir = TempDir::new("platform-portable");
+        assert_eq!(mods_dir_for_install(&dir).unwrap(), dir.join("mods"));
+    }
+
+    #[test]
+    fn write_data_is_read_from_config_ini() {
+        let dir = TempDir::new("platform-config");
+        fs::write(dir.join("config-path.cfg"), "config-path=__PATH__executable__/../../settings\nuse-system-read-write-data-directories=false\n").unwrap();
+        fs::create_dir_all(dir.join("settings")).unwrap();
+        let ini = "; version=12\n[other]\nwrite-data=/wrong\n\n[path]\nread-data=__PATH__executable__/../../data\nwrite-data = __PATH__executable__/../../userdata\n";
+        fs::write(dir.join("settings/config.ini"), ini).unwrap();
+
+        assert_eq!(mods_dir_for_install(&dir).unwrap(), dir.join("userdata/mods"));
+    }
+
+    #[test]
+    fn missing_write_data_falls_back_to_the_install() {
+        let dir = TempDir::new("platform-no-write-data");
+        fs::write(dir.join("config-path.cfg"), "use-system-read-write-data-directories=false\n").unwrap();
+        fs::create_dir_all(dir.join("config")).unwrap();
+        fs::write(dir.join("config/config.ini"), "[path]\nread-data=__PATH__executable__/../../data\n").unwrap();
+
+        assert_eq!(mods_dir_for_install(&dir).unwrap(), dir.join("mods"));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,207 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::mod_info::{Dependency, Version};
+use crate::platform::factorio_user_data_dir;
+
+/// Mod portal used when neither `--portal-url`, `$FACTORIO_PORTAL_URL` nor `[portal] url` is set
+pub const DEFAULT_PORTAL_URL: &str = "https://mods.factorio.com";
+
+/// Environment variable that overrides the mod portal URL
+pub const PORTAL_URL_ENV: &str = "FACTORIO_PORTAL_URL";
+
+/// Environment variable holding the mod portal API key used for publishing
+pub const API_KEY_ENV: &str = "FACTORIO_API_KEY";
+
+/// A mod as listed by `/api/mods/{name}/full`
+#[derive(Deserialize)]
+pub struct PortalMod {
+    pub name: String,
+    pub releases: Vec<Release>,
+}
+
+/// One uploaded version of a mod
+#[derive(Deserialize)]
+pub struct Release {
+    pub download_url: String,
+    pub file_name: String,
+    pub info_json: ReleaseInfo,
+    pub version: String,
+    pub sha1: String,
+}
+
+/// The parts of a release's info.json the portal exposes
+#[derive(Deserialize)]
+pub struct ReleaseInfo {
+    pub factorio_version: String,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+impl Release {
+    pub fn parsed_version(&self) -> Option<Version> {
+        self.version.parse().ok()
+    }
+
+    /// Whether the release is made for the game series `major.minor`
+    fn is_for_series(&self, (major, minor): (u16, u16)) -> bool {
+        self.info_json.factorio_version.split_once('.').is_some_and(|(ma, mi)| ma.parse() == Ok(major) && mi.parse() == Ok(minor))
+    }
+}
+
+impl PortalMod {
+    /// Newest release for the game series `major.minor` that satisfies `dep`'s version constraint
+    pub fn best_release(&self, dep: &Dependency, series: (u16, u16)) -> Option<&Release> {
+        self.releases
+            .iter()
+            .filter(|r| r.is_for_series(series))
+            .filter_map(|r| Some((r.parsed_version()?, r)))
+            .filter(|(version, _)| dep.constraint.is_none_or(|(op, wanted)| op.matches(*version, wanted)))
+            .max_by_key(|(version, _)| *version)
+            .map(|(_, r)| r)
+    }
+}
+
+/// Login used for downloads, as stored by the game after logging in
+pub struct Credentials {
+    pub username: String,
+    pub token: String,
+}
+
+impl Credentials {
+    /// `$FACTORIO_USERNAME`/`$FACTORIO_TOKEN`, else `service-username`/`service-token` from player-data.json
+    /// next to `mods_dir`, else from the one in the user data directory
+    pub fn load(mods_dir: &Path) -> Result<Self> {
+        if let (Ok(username), Ok(token)) = (std::env::var("FACTORIO_USERNAME"), std::env::var("FACTORIO_TOKEN")) {
+            return Ok(Self { username, token });
+        }
+
+        let mut candidates: Vec<_> = mods_dir.parent().map(|dir| dir.join("player-data.json")).into_iter().collect();
+        candidates.push(factorio_user_data_dir()?.join("player-data.json"));
+        for path in candidates.iter().filter(|p| p.exists()) {
+            let content = fs::read_to_string(path)?;
+            let json: serde_json::Value = serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?;
+            let field = |key: &str| json.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(str::to_string);
+            if let (Some(username), Some(token)) = (field("service-username"), field("service-token")) {
+                return Ok(Self { username, token });
+            }
+        }
+        bail!("No mod portal login found: log in from the game once, or set FACTORIO_USERNAME and FACTORIO_TOKEN")
+    }
+}
+
+/// Client for the mod portal's public API
+pub struct Portal {
+    base_url: String,
+}
+
+impl Portal {
+    pub fn new(base_url: &str) -> Self {
+        Self { base_url: base_url.trim_end_matches('/').to_string() }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Full details of a mod, including every release and its info.json
+    pub fn mod_full(&self, name: &str) -> Result<PortalMod> {
+        let url = format!("{}/api/mods/{}/full", self.base_url, encode_path_segment(name));
+        match ureq::get(&url).call() {
+            Ok(response) => response.into_json().with_context(|| format!("Unexpected response from {}", url)),
+            Err(ureq::Error::Status(404, _)) => bail!("Mod `{}` not found on {}", name, self.base_url),
+            Err(e) => Err(e).with_context(|| format!("Failed to query {}", url)),
+        }
+    }
+
+    /// Start uploading a new release of an existing mod; returns the URL to send the zip to
+    pub fn init_upload(&self, api_key: &str, mod_name: &str) -> Result<String> {
+        let url = format!("{}/api/v2/mods/releases/init_upload", self.base_url);
+        let response = api_call(ureq::post(&url).set("Authorization", &format!("Bearer {}", api_key)).send_form(&[("mod", mod_name)]), &url)?;
+        let upload_url = response.get("upload_url").and_then(|v| v.as_str());
+        upload_url.map(str::to_string).with_context(|| format!("No upload_url in the response from {}", url))
+    }
+
+    /// Send the release zip to the URL handed out by `init_upload`
+    pub fn finish_upload(&self, upload_url: &str, zip_path: &Path) -> Result<()> {
+        let file_name = zip_path.file_name().unwrap_or_default().to_string_lossy();
+        let (content_type, body) = multipart_file("file", &file_name, &fs::read(zip_path)?);
+        api_call(ureq::post(upload_url).set("Content-Type", &content_type).send_bytes(&body), upload_url)?;
+        Ok(())
+    }
+
+    /// Replace the summary and/or description shown on the mod's portal page
+    pub fn edit_details(&self, api_key: &str, mod_name: &str, summary: Option<&str>, description: Option<&str>) -> Result<()> {
+        let url = format!("{}/api/v2/mods/edit_details", self.base_url);
+        let mut form = vec![("mod", mod_name)];
+        form.extend(summary.map(|s| ("summary", s)));
+        form.extend(description.map(|d| ("description", d)));
+        api_call(ureq::post(&url).set("Authorization", &format!("Bearer {}", api_key)).send_form(&form), &url)?;
+        Ok(())
+    }
+
+    /// Download a release into `dest`, checking its sha1 before anything is written there
+    pub fn download(&self, release: &Release, credentials: &Credentials, dest: &Path) -> Result<()> {
+        let url = format!("{}{}", self.base_url, release.download_url);
+        let response = ureq::get(&url)
+            .query("username", &credentials.username)
+            .query("token", &credentials.token)
+            .call()
+            .with_context(|| format!("Failed to download {}", release.file_name))?;
+
+        let mut bytes = Vec::new();
+        response.into_reader().read_to_end(&mut bytes)?;
+        let sha1 = sha1_smol::Sha1::from(&bytes).digest().to_string();
+        if !sha1.eq_ignore_ascii_case(&release.sha1) {
+            bail!("Checksum mismatch for {}: expected sha1 {}, got {}", release.file_name, release.sha1, sha1);
+        }
+
+        let partial = dest.with_extension("zip.part");
+        fs::write(&partial, &bytes)?;
+        fs::rename(&partial, dest)?;
+        Ok(())
+    }
+}
+
+/// Percent-encode `segment` for use as one URL path segment; mod names may contain spaces
+fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// JSON body of a portal API response, turning the portal's `{"error", "message"}` replies into errors
+fn api_call(result: std::result::Result<ureq::Response, ureq::Error>, url: &str) -> Result<serde_json::Value> {
+    match result {
+        Ok(response) => response.into_json().with_context(|| format!("Unexpected response from {}", url)),
+        Err(ureq::Error::Status(code, response)) => {
+            let body: serde_json::Value = response.into_json().unwrap_or_default();
+            let field = |key: &str| body.get(key).and_then(|v| v.as_str()).map(str::to_string);
+            let message = field("message").or_else(|| field("error")).unwrap_or_else(|| "no details".to_string());
+            bail!("{} returned {}: {}", url, code, message)
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to reach {}", url)),
+    }
+}
+
+/// A `multipart/form-data` body holding one file field, with its content type
+fn multipart_file(field: &str, file_name: &str, bytes: &[u8]) -> (String, Vec<u8>) {
+    let boundary = format!("cargo-factorio-{}", sha1_smol::Sha1::from(bytes).digest());
+    let mut body = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: application/zip\r\n\r\n",
+        boundary, field, file_name
+    )
+    .into_bytes();
+    body.extend_from_slice(bytes);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    (format!("multipart/form-data; boundary={}", boundary), body)
+}
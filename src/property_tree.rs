@@ -0,0 +1,279 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Number, Value};
+
+/// Factorio's binary "property tree", the format of mod-settings.dat
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyTree {
+    None,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    List(Vec<PropertyTree>),
+    /// Ordered key/value pairs; order is kept so files round-trip byte for byte
+    Dictionary(Vec<(String, PropertyTree)>),
+    SignedInteger(i64),
+    UnsignedInteger(u64),
+}
+
+impl PropertyTree {
+    pub fn get(&self, key: &str) -> Option<&PropertyTree> {
+        match self {
+            PropertyTree::Dictionary(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut PropertyTree> {
+        match self {
+            PropertyTree::Dictionary(entries) => entries.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Set `key` in a dictionary, replacing an existing value in place
+    pub fn insert(&mut self, key: &str, value: PropertyTree) -> Result<()> {
+        let PropertyTree::Dictionary(entries) = self else {
+            bail!("cannot set {:?} on a non-dictionary node", key);
+        };
+        match entries.iter_mut().find(|(k, _)| k == key) {
+            Some((_, existing)) => *existing = value,
+            None => entries.push((key.to_string(), value)),
+        }
+        Ok(())
+    }
+
+    /// Remove every dictionary entry whose key matches, returning the removed keys
+    pub fn remove_where(&mut self, mut matches: impl FnMut(&str) -> bool) -> Vec<String> {
+        let PropertyTree::Dictionary(entries) = self else {
+            return Vec::new();
+        };
+        let mut removed = Vec::new();
+        entries.retain(|(k, _)| {
+            let hit = matches(k);
+            if hit {
+                removed.push(k.clone());
+            }
+            !hit
+        });
+        removed
+    }
+
+    /// Lossy JSON view for humans: dictionaries become objects, every number a JSON number
+    pub fn to_json(&self) -> Value {
+        match self {
+            PropertyTree::None => Value::Null,
+            PropertyTree::Bool(b) => Value::Bool(*b),
+            PropertyTree::Number(n) => Number::from_f64(*n).map_or(Value::Null, Value::Number),
+            PropertyTree::String(s) => Value::String(s.clone()),
+            PropertyTree::List(items) => Value::Array(items.iter().map(PropertyTree::to_json).collect()),
+            PropertyTree::Dictionary(entries) => {
+                Value::Object(entries.iter().map(|(k, v)| (k.clone(), v.to_json())).collect::<Map<_, _>>())
+            }
+            PropertyTree::SignedInteger(n) => Value::from(*n),
+            PropertyTree::UnsignedInteger(n) => Value::from(*n),
+        }
+    }
+
+    /// Convert JSON into a tree; numbers become `Number` (a double), as Factorio stores most settings
+    pub fn from_json(value: &Value) -> PropertyTree {
+        match value {
+            Value::Null => PropertyTree::None,
+            Value::Bool(b) => PropertyTree::Bool(*b),
+            Value::Number(n) => PropertyTree::Number(n.as_f64().unwrap_or_default()),
+            Value::String(s) => PropertyTree::String(s.clone()),
+            Value::Array(items) => PropertyTree::List(items.iter().map(PropertyTree::from_json).collect()),
+            Value::Object(map) => PropertyTree::Dictionary(map.iter().map(|(k, v)| (k.clone(), PropertyTree::from_json(v))).collect()),
+        }
+    }
+
+    fn type_id(&self) -> u8 {
+        match self {
+            PropertyTree::None => 0,
+            PropertyTree::Bool(_) => 1,
+            PropertyTree::Number(_) => 2,
+            PropertyTree::String(_) => 3,
+            PropertyTree::List(_) => 4,
+            PropertyTree::Dictionary(_) => 5,
+            PropertyTree::SignedInteger(_) => 6,
+            PropertyTree::UnsignedInteger(_) => 7,
+        }
+    }
+
+    /// Decode one tree from the reader's current position
+    pub fn read(reader: &mut Reader<'_>) -> Result<PropertyTree> {
+        let type_id = reader.u8()?;
+        let _any_type = reader.u8()?;
+        Ok(match type_id {
+            0 => PropertyTree::None,
+            1 => PropertyTree::Bool(reader.u8()? != 0),
+            2 => PropertyTree::Number(f64::from_le_bytes(reader.array()?)),
+            3 => PropertyTree::String(reader.string()?),
+            4 => {
+                let count = reader.u32()?;
+                let mut items = Vec::new();
+                for _ in 0..count {
+                    let _key = reader.string()?;
+                    items.push(PropertyTree::read(reader)?);
+                }
+                PropertyTree::List(items)
+            }
+            5 => {
+                let count = reader.u32()?;
+                let mut entries = Vec::new();
+                for _ in 0..count {
+                    let key = reader.string()?;
+                    entries.push((key, PropertyTree::read(reader)?));
+                }
+                PropertyTree::Dictionary(entries)
+            }
+            6 => PropertyTree::SignedInteger(i64::from_le_bytes(reader.array()?)),
+            7 => PropertyTree::UnsignedInteger(u64::from_le_bytes(reader.array()?)),
+            other => bail!("unknown property tree type {} at byte {}", other, reader.pos - 2),
+        })
+    }
+
+    /// Encode this tree, appending to `out`
+    pub fn write(&self, out: &mut Vec<u8>) {
+        out.push(self.type_id());
+        out.push(0); // "any type" flag, always false in files written by the game
+        match self {
+            PropertyTree::None => {}
+            PropertyTree::Bool(b) => out.push(u8::from(*b)),
+            PropertyTree::Number(n) => out.extend_from_slice(&n.to_le_bytes()),
+            PropertyTree::String(s) => write_string(out, s),
+            PropertyTree::List(items) => {
+                out.extend_from_slice(&(items.len() as u32).to_le_bytes());
+                for item in items {
+                    write_string(out, "");
+                    item.write(out);
+                }
+            }
+            PropertyTree::Dictionary(entries) => {
+                out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+                for (key, value) in entries {
+                    write_string(out, key);
+                    value.write(out);
+                }
+            }
+            PropertyTree::SignedInteger(n) => out.extend_from_slice(&n.to_le_bytes()),
+            PropertyTree::UnsignedInteger(n) => out.extend_from_slice(&n.to_le_bytes()),
+        }
+    }
+}
+
+/// Strings are an "is empty" flag, then a space-optimized length (u8, or 255 + u32) and UTF-8 bytes
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    out.push(u8::from(s.is_empty()));
+    if s.is_empty() {
+        return;
+    }
+    if s.len() < 255 {
+        out.push(s.len() as u8);
+    } else {
+        out.push(255);
+        out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    }
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Little-endian cursor over a byte buffer, with positions in error messages
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.bytes.len());
+        let Some(end) = end else {
+            bail!("unexpected end of data at byte {} (wanted {} more)", self.pos, len);
+        };
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("slice has requested length"))
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        if self.u8()? != 0 {
+            return Ok(String::new());
+        }
+        let len = match self.u8()? {
+            255 => self.u32()? as usize,
+            short => short as usize,
+        };
+        let start = self.pos;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).with_context(|| format!("invalid UTF-8 in string at byte {}", start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hand-encoded tree covering every type, an empty key and a string needing the long length form
+    fn sample_bytes() -> Vec<u8> {
+        let mut out = vec![5, 0];
+        out.extend_from_slice(&7u32.to_le_bytes());
+        let mut entry = |key: &str, value: &[u8]| {
+            write_string(&mut out, key);
+            out.extend_from_slice(value);
+        };
+        entry("none", &[0, 0]);
+        entry("bool", &[1, 0, 1]);
+        entry("number", &[[2, 0].as_slice(), &1.5f64.to_le_bytes()].concat());
+        let long = "x".repeat(300);
+        let mut string = vec![3, 0];
+        write_string(&mut string, &long);
+        entry("string", &string);
+        entry("list", &[[4, 0].as_slice(), &1u32.to_le_bytes(), &[1], &[6, 0], &(-3i64).to_le_bytes()].concat());
+        entry("unsigned", &[[7, 0].as_slice(), &u64::MAX.to_le_bytes()].concat());
+        entry("", &[[5, 0].as_slice(), &0u32.to_le_bytes()].concat());
+        out
+    }
+
+    #[test]
+    fn write_reproduces_read_bytes() {
+        let bytes = sample_bytes();
+        let mut reader = Reader::new(&bytes);
+        let tree = PropertyTree::read(&mut reader).unwrap();
+        assert!(reader.is_at_end());
+        assert_eq!(tree.get("string"), Some(&PropertyTree::String("x".repeat(300))));
+
+        let mut written = Vec::new();
+        tree.write(&mut written);
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn truncated_input_reports_position() {
+        let bytes = sample_bytes();
+        let mut reader = Reader::new(&bytes[..bytes.len() - 2]);
+        let err = PropertyTree::read(&mut reader).unwrap_err().to_string();
+        assert!(err.starts_with("unexpected end of data at byte"), "{}", err);
+    }
+}
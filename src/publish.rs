@@ -0,0 +1,68 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::build::{build_mods, BuiltMod};
+use crate::config::Settings;
+use crate::portal::Portal;
+use crate::workspace::select_mods;
+
+/// README looked up in each mod folder by `publish --readme`
+const README_FILE: &str = "README.md";
+
+/// Longest summary the portal accepts
+const MAX_SUMMARY_LEN: usize = 500;
+
+/// Upload each selected mod's release zip to the mod portal, optionally refreshing its description from README.md
+pub fn publish_mods(mod_path: Option<PathBuf>, settings: &Settings, portal_url: Option<String>, no_build: bool, readme: bool) -> Result<()> {
+    let api_key = settings.portal_api_key()?;
+    let portal = Portal::new(&settings.portal_url(portal_url));
+    settings.log(&format!("🌐 Mod portal: {}", portal.base_url()));
+
+    let releases = if no_build { existing_zips(mod_path, settings)? } else { build_mods(mod_path, settings)? };
+    for release in &releases {
+        let info = &release.module.info;
+        let details = if readme { Some(readme_details(&release.module.root)?) } else { None };
+
+        let upload_url = portal.init_upload(&api_key, &info.name)?;
+        settings.log(&format!("📤 Uploading {} to {}", release.zip_path.display(), upload_url));
+        portal.finish_upload(&upload_url, &release.zip_path)?;
+        println!("🚀 Published {} to {}", info.zip_name(), portal.base_url());
+
+        if let Some((summary, description)) = details {
+            portal.edit_details(&api_key, &info.name, Some(&summary), Some(&description))?;
+            println!("📝 Updated the portal description of {} from {}", info.name, README_FILE);
+        }
+    }
+    Ok(())
+}
+
+/// Release zips that a previous `build` left in each mod's output directory
+fn existing_zips(mod_path: Option<PathBuf>, settings: &Settings) -> Result<Vec<BuiltMod>> {
+    let mut releases = Vec::new();
+    for module in select_mods(mod_path, settings)? {
+        let zip_path = settings.build_config(&module.info.name).out_dir.join(format!("{}.zip", module.info.zip_name()));
+        if !zip_path.exists() {
+            bail!("{} not found; run `cargo factorio build` first or drop --no-build", zip_path.display());
+        }
+        releases.push(BuiltMod { module, zip_path });
+    }
+    Ok(releases)
+}
+
+/// Portal summary and description from the mod's README: the first plain paragraph, and the whole file
+fn readme_details(mod_root: &Path) -> Result<(String, String)> {
+    let path = mod_root.join(README_FILE);
+    let description = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let summary = description
+        .split("\n\n")
+        .map(|paragraph| paragraph.lines().map(str::trim).collect::<Vec<_>>().join(" "))
+        .find(|paragraph| !paragraph.is_empty() && !paragraph.starts_with(['#', '!', '[', '<', '`']))
+        .with_context(|| format!("No summary paragraph found in {}", path.display()))?;
+    let summary = match summary.char_indices().nth(MAX_SUMMARY_LEN) {
+        Some((cut, _)) => summary[..cut].to_string(),
+        None => summary,
+    };
+    Ok((summary, description))
+}
@@ -0,0 +1,54 @@
+//! Fixtures shared by the unit tests and, through `#[path]`, the integration tests in `tests/`.
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// An empty directory under the system temp dir, deleted again when dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("cargo-factorio-{}-{}-{}", name, std::process::id(), unique));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        self
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Write a minimal mod named `name` into `dir` with the given dependencies
+pub fn write_mod(dir: &Path, name: &str, dependencies: &[&str]) {
+    fs::create_dir_all(dir).unwrap();
+    let info = serde_json::json!({
+        "name": name,
+        "version": "1.0.0",
+        "title": name,
+        "author": "tester",
+        "factorio_version": "2.0",
+        "dependencies": dependencies,
+    });
+    fs::write(dir.join("info.json"), serde_json::to_string_pretty(&info).unwrap()).unwrap();
+}
@@ -0,0 +1,198 @@
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::compat::{BASE_MOD, DLC_MODS};
+use crate::config::{DiscoveryConfig, Settings};
+use crate::mod_info::{detect_all_mod_roots, resolve_mod_paths, Dependency, DependencyKind, Info, Version};
+
+/// A mod selected for building, with its parsed info.json
+pub struct WorkspaceMod {
+    pub root: PathBuf,
+    pub info: Info,
+}
+
+/// Find the mods a command should act on (one explicit path, or all discovered) in build order
+pub fn select_mods(mod_path: Option<PathBuf>, settings: &Settings) -> Result<Vec<WorkspaceMod>> {
+    let cwd = std::env::current_dir()?;
+    let explicit = mod_path.is_some();
+    let mods = resolve_mod_paths(mod_path, &cwd, &settings.discovery)?;
+
+    if mods.is_empty() {
+        bail!("No mods found. Place an info.json in the repo root or in subfolders.");
+    }
+
+    let mods = resolve_build_order(mods)?;
+    check_unselected_dependencies(&mods, &cwd, settings, explicit)?;
+    let order: Vec<&str> = mods.iter().map(|m| m.info.name.as_str()).collect();
+    settings.log(&format!("🧭 Build order: {}", order.join(" → ")));
+    Ok(mods)
+}
+
+/// Fail when a selected mod requires a workspace mod that `[discovery]` include/exclude filtered out, since
+/// the build would silently pick up whatever copy is installed. An explicit mod path only warns.
+fn check_unselected_dependencies(mods: &[WorkspaceMod], cwd: &Path, settings: &Settings, explicit: bool) -> Result<()> {
+    let unfiltered = DiscoveryConfig { max_depth: settings.discovery.max_depth, ..DiscoveryConfig::default() };
+    // Problems with mods outside the selection (e.g. duplicate names) are reported once they are selected
+    let Ok(roots) = detect_all_mod_roots(cwd, &unfiltered) else { return Ok(()) };
+    let mut unselected: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for root in roots {
+        match Info::load_from_dir(&root) {
+            Ok(info) => {
+                unselected.insert(info.name, root);
+            }
+            Err(e) => eprintln!(
+                "⚠️  Can't tell whether the selected mods depend on {}: its info.json doesn't parse ({})",
+                root.display(),
+                e
+            ),
+        }
+    }
+    // A game folder below the workspace holds `base` and the expansion mods, which are never workspace mods
+    unselected.retain(|name, _| name != BASE_MOD && !DLC_MODS.contains(&name.as_str()));
+    for m in mods {
+        unselected.remove(&m.info.name);
+    }
+
+    let mut missing = Vec::new();
+    for m in mods {
+        for dep in m.info.parsed_dependencies()?.into_iter().filter(Dependency::is_required) {
+            if let Some(root) = unselected.get(&dep.name) {
+                missing.push(format!("`{}` requires workspace mod `{}` ({}), which is not selected", m.info.name, dep.name, root.display()));
+            }
+        }
+    }
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if explicit {
+        for problem in &missing {
+            eprintln!("⚠️  {}; the installed copy will be used", problem);
+        }
+        return Ok(());
+    }
+    bail!("Missing workspace dependencies:\n  {}\nInclude them in [discovery] or pass a mod path explicitly", missing.join("\n  "))
+}
+
+/// Load every mod root and order them so that mods come after the workspace mods they depend on.
+///
+/// Every dependency except `!` incompatibilities counts as an edge, so optional and `~`
+/// dependencies on a sibling still get built first. Dependencies on mods outside the
+/// selection are left to the game or to `check_unselected_dependencies`. Ties are broken by mod name, so the order is stable.
+/// Names are unique here: discovery already rejects two mods with the same name.
+fn resolve_build_order(roots: Vec<PathBuf>) -> Result<Vec<WorkspaceMod>> {
+    let mut mods: BTreeMap<String, WorkspaceMod> = BTreeMap::new();
+    for root in roots {
+        let info = Info::load_from_dir(&root)
+            .with_context(|| format!("Failed to parse {}", root.join("info.json").display()))?;
+        mods.insert(info.name.clone(), WorkspaceMod { root, info });
+    }
+
+    let mut edges: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (name, m) in &mods {
+        let mut deps = BTreeSet::new();
+        for dep in m.info.parsed_dependencies()? {
+            if dep.kind == DependencyKind::Incompatible {
+                continue;
+            }
+            let Some(target) = mods.get(&dep.name) else { continue };
+            if let Some((op, wanted)) = dep.constraint {
+                let found: Version = target
+                    .info
+                    .version
+                    .parse()
+                    .with_context(|| format!("Invalid version in {}", target.root.join("info.json").display()))?;
+                if !op.matches(found, wanted) {
+                    bail!("`{}` requires `{}`, but the workspace has {} {}", name, dep, target.info.name, found);
+                }
+            }
+            deps.insert(dep.name);
+        }
+        edges.insert(name.clone(), deps);
+    }
+
+    let order = topological_order(&edges)?;
+    Ok(order.into_iter().filter_map(|name| mods.remove(&name)).collect())
+}
+
+/// Kahn's algorithm over `mod -> dependencies`, picking the alphabetically first ready mod each step
+fn topological_order(edges: &BTreeMap<String, BTreeSet<String>>) -> Result<Vec<String>> {
+    let mut remaining: BTreeMap<&str, usize> = edges.iter().map(|(name, deps)| (name.as_str(), deps.len())).collect();
+    let mut ready: BTreeSet<&str> = remaining.iter().filter(|(_, n)| **n == 0).map(|(name, _)| *name).collect();
+    let mut order = Vec::with_capacity(edges.len());
+
+    while let Some(name) = ready.pop_first() {
+        remaining.remove(name);
+        order.push(name.to_string());
+        for (dependent, deps) in edges {
+            if deps.contains(name)
+                && let Some(count) = remaining.get_mut(dependent.as_str())
+            {
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+    }
+
+    if !remaining.is_empty() {
+        let start = remaining.keys().next().copied().unwrap_or_default();
+        bail!("Dependency cycle between workspace mods: {}", find_cycle(edges, &remaining, start).join(" → "));
+    }
+    Ok(order)
+}
+
+/// Follow unresolved dependencies from `start` until a mod repeats, returning that loop
+fn find_cycle<'a>(edges: &'a BTreeMap<String, BTreeSet<String>>, remaining: &BTreeMap<&str, usize>, start: &'a str) -> Vec<&'a str> {
+    let mut path = vec![start];
+    let mut current = start;
+    loop {
+        // Every mod left after Kahn's algorithm has at least one dependency that is also left
+        let Some(next) = edges[current].iter().find(|d| remaining.contains_key(d.as_str())) else {
+            return path;
+        };
+        if let Some(i) = path.iter().position(|p| *p == next.as_str()) {
+            path.push(next);
+            return path.split_off(i);
+        }
+        path.push(next);
+        current = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{write_mod, TempDir};
+
+    /// Write each mod into its own folder of `dir`, returning the folders
+    fn mod_roots(dir: &TempDir, mods: &[(&str, &[&str])]) -> Vec<PathBuf> {
+        mods.iter()
+            .map(|(name, dependencies)| {
+                write_mod(&dir.join(name), name, dependencies);
+                dir.join(name)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn build_order_puts_dependencies_first() {
+        let dir = TempDir::new("order");
+        let roots = mod_roots(
+            &dir,
+            &[("app", &["lib >= 1.0", "? extra"]), ("extra", &["lib"]), ("lib", &["base"]), ("rival", &["! app"])],
+        );
+        let order: Vec<_> = resolve_build_order(roots).unwrap().into_iter().map(|m| m.info.name).collect();
+        assert_eq!(order, ["lib", "extra", "app", "rival"]);
+    }
+
+    #[test]
+    fn build_order_reports_the_cycle() {
+        let dir = TempDir::new("cycle");
+        let roots = mod_roots(&dir, &[("a", &["b"]), ("b", &["c"]), ("c", &["? b"]), ("d", &[])]);
+        let error = resolve_build_order(roots).err().unwrap().to_string();
+        assert_eq!(error, "Dependency cycle between workspace mods: b → c → b");
+    }
+}
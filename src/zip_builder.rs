@@ -1,46 +1,88 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{DirEntry, Walk, WalkBuilder};
+use std::collections::HashSet;
 use std::fs;
 use std::io::{self, Seek, Write};
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
 use zip::write::FileOptions;
-use zip::CompressionMethod;
+use zip::{CompressionMethod, DateTime};
 
-use crate::config::BuildConfig;
+use crate::config::{BuildConfig, GitFilter};
+use crate::git;
+use crate::time::civil_from_days;
+
+/// Per-directory ignore file honored while building a zip (gitignore syntax).
+pub const IGNORE_FILE: &str = ".factorioignore";
+
+/// Deflate level used in reproducible mode, pinned so zlib defaults can't drift
+const REPRODUCIBLE_DEFLATE_LEVEL: i64 = 6;
 
 /// Build a ZIP with `<name>_<version>/` top-level and forward slashes.
+///
+/// The zip is written under a temporary name and only moved into place once complete, so a failed
+/// build never leaves a truncated zip for `install` or `publish --no-build` to pick up.
 pub fn build_zip(mod_root: &Path, out_zip: &Path, top: &str, config: &BuildConfig) -> Result<()> {
+    // A zip from an earlier build must not outlive a failed one either
     prepare_output_file(out_zip)?;
+    let (dir_opts, file_opts) = entry_options(mod_root, config)?;
+    let entries = mod_entries(mod_root, config)?;
 
-    let file = fs::File::create(out_zip)?;
-    let mut zip = zip::ZipWriter::new(file);
+    let partial = out_zip.with_extension("zip.part");
+    let written = write_zip(&partial, mod_root, top, entries, (dir_opts, file_opts), config);
+    if written.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    written?;
+    fs::rename(&partial, out_zip).with_context(|| format!("Failed to move {} into place", partial.display()))?;
 
-    let dir_opts: FileOptions<()> = FileOptions::default();
-    let file_opts: FileOptions<()> = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    eprintln!("📦 Built {}", out_zip.display());
+    Ok(())
+}
 
-    for entry in WalkDir::new(mod_root).follow_links(false).into_iter().filter_map(Result::ok) {
-        if entry.path() == mod_root {
-            continue;
-        }
+fn write_zip(
+    path: &Path,
+    mod_root: &Path,
+    top: &str,
+    entries: impl Iterator<Item = DirEntry>,
+    (dir_opts, file_opts): (FileOptions<'static, ()>, FileOptions<'static, ()>),
+    config: &BuildConfig,
+) -> Result<()> {
+    let file = fs::File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut zip = zip::ZipWriter::new(file);
 
-        let Some(zip_path) = create_zip_path(entry.path(), mod_root, top, config.excludes) else {
+    for entry in entries {
+        let Some(zip_path) = create_zip_path(entry.path(), mod_root, top) else {
             continue;
         };
 
-        if entry.file_type().is_dir() {
+        if entry.file_type().is_some_and(|t| t.is_dir()) {
             add_directory_to_zip(&mut zip, &zip_path, dir_opts, config)?;
         } else {
             add_file_to_zip(&mut zip, entry.path(), &zip_path, file_opts, config)?;
         }
     }
 
-    add_default_thumbnail_if_missing(&mut zip, mod_root, config.default_thumbnail.as_deref(), top, config)?;
+    add_default_thumbnail_if_missing(&mut zip, mod_root, config.default_thumbnail.as_deref(), top, file_opts, config)?;
 
     zip.finish()?;
-    println!("📦 Built {}", out_zip.display());
     Ok(())
 }
 
+/// Files and folders below `mod_root` that belong in the mod, after every exclude and git filter
+pub fn mod_entries(mod_root: &Path, config: &BuildConfig) -> Result<impl Iterator<Item = DirEntry>> {
+    let excludes = build_exclude_matcher(mod_root, &config.excludes, &config.out_dir)?;
+    let tracked = match config.git_filter {
+        GitFilter::TrackedOnly => Some(tracked_paths(mod_root)?),
+        _ => None,
+    };
+
+    let root = mod_root.to_path_buf();
+    Ok(walk_mod_files(mod_root, excludes, tracked, config)
+        .filter_map(Result::ok)
+        .filter(move |entry| entry.path() != root))
+}
+
 /// Prepare the output file by creating parent directories and removing existing file
 fn prepare_output_file(out_zip: &Path) -> Result<()> {
     if let Some(parent) = out_zip.parent() {
@@ -52,15 +94,129 @@ fn prepare_output_file(out_zip: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Create ZIP path for a file, returning None if it should be excluded
-fn create_zip_path(path: &Path, mod_root: &Path, top: &str, excludes: &[&str]) -> Option<String> {
-    let rel = path.strip_prefix(mod_root).ok()?;
-    
-    // Check if this path should be excluded
-    if should_exclude_path(rel, excludes) {
-        return None;
+/// Walk a mod root, skipping configured excludes and anything matched by a `.factorioignore`.
+///
+/// Ignore files are picked up at every depth and in the folders above the mod, so a nested
+/// `.factorioignore` only applies to its own directory and below, exactly like a nested
+/// `.gitignore`. With git filtering on, `.gitignore` rules apply the same way, and `tracked`
+/// further restricts the walk to files and directories git knows about.
+/// Reproducible builds walk in sorted order so entries land in the zip the same way every time.
+fn walk_mod_files(mod_root: &Path, excludes: Gitignore, tracked: Option<HashSet<PathBuf>>, config: &BuildConfig) -> Walk {
+    let use_git = config.git_filter != GitFilter::Off;
+
+    let mut builder = WalkBuilder::new(mod_root);
+    if config.reproducible {
+        builder.sort_by_file_name(|a, b| a.cmp(b));
+    }
+
+    builder
+        .standard_filters(false)
+        .follow_links(false)
+        .git_ignore(use_git)
+        .git_exclude(use_git)
+        .git_global(use_git)
+        .parents(true)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            if excludes.matched(entry.path(), is_dir).is_ignore() {
+                return false;
+            }
+            tracked.as_ref().is_none_or(|paths| paths.contains(entry.path()))
+        })
+        .build()
+}
+
+/// Options for directory and file entries; fully pinned down in reproducible mode
+fn entry_options(mod_root: &Path, config: &BuildConfig) -> Result<(FileOptions<'static, ()>, FileOptions<'static, ()>)> {
+    let dir_opts: FileOptions<()> = FileOptions::default();
+    let file_opts: FileOptions<()> = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    if !config.reproducible {
+        return Ok((dir_opts, file_opts));
+    }
+
+    let timestamp = reproducible_timestamp(mod_root)?;
+    config.log(&format!("🕒 Reproducible timestamp {}", timestamp));
+
+    Ok((
+        dir_opts.last_modified_time(timestamp).unix_permissions(0o755),
+        file_opts
+            .last_modified_time(timestamp)
+            .unix_permissions(0o644)
+            .compression_level(Some(REPRODUCIBLE_DEFLATE_LEVEL)),
+    ))
+}
+
+/// Entry timestamp: `SOURCE_DATE_EPOCH`, else the last commit touching the mod, else 1980-01-01
+fn reproducible_timestamp(mod_root: &Path) -> Result<DateTime> {
+    let secs = match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(value) => Some(
+            value.trim().parse::<i64>().with_context(|| format!("SOURCE_DATE_EPOCH must be a Unix timestamp in seconds, got `{}`", value))?,
+        ),
+        Err(_) => git::last_commit_time(mod_root).ok(),
+    };
+    Ok(secs.map(zip_datetime_from_unix).unwrap_or_default())
+}
+
+/// Unix timestamps of the first and last second a ZIP date can hold (1980-01-01, 2107-12-31 23:59:59 UTC)
+const ZIP_TIME_RANGE: (i64, i64) = (315_532_800, 4_354_819_199);
+
+/// Convert a Unix timestamp (UTC) to a ZIP date, clamping to the format's 1980–2107 range
+fn zip_datetime_from_unix(secs: i64) -> DateTime {
+    let secs = secs.clamp(ZIP_TIME_RANGE.0, ZIP_TIME_RANGE.1);
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let secs_of_day = secs.rem_euclid(86_400);
+
+    DateTime::from_date_and_time(
+        u16::try_from(year).unwrap_or(0),
+        month,
+        day,
+        (secs_of_day / 3600) as u8,
+        (secs_of_day % 3600 / 60) as u8,
+        (secs_of_day % 60) as u8,
+    )
+    .unwrap_or_default()
+}
+
+/// Tracked files under the mod root plus every directory leading to them
+fn tracked_paths(mod_root: &Path) -> Result<HashSet<PathBuf>> {
+    let files = git::tracked_files(mod_root)?;
+    let mut paths = HashSet::new();
+
+    for file in files {
+        for ancestor in file.ancestors().skip(1) {
+            if ancestor == mod_root || !paths.insert(ancestor.to_path_buf()) {
+                break;
+            }
+        }
+        paths.insert(file);
+    }
+
+    Ok(paths)
+}
+
+/// Compile the configured exclude patterns into a matcher rooted at the mod folder.
+/// The output directory is excluded too when it lives inside the mod.
+fn build_exclude_matcher(mod_root: &Path, patterns: &[String], out_dir: &Path) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(mod_root);
+    for pattern in patterns {
+        builder.add_line(None, pattern)?;
     }
 
+    let out_dir = std::path::absolute(out_dir)?;
+    if let Ok(rel) = out_dir.strip_prefix(mod_root)
+        && !rel.as_os_str().is_empty()
+    {
+        builder.add_line(None, &format!("/{}/", rel.to_string_lossy().replace('\\', "/")))?;
+    }
+    Ok(builder.build()?)
+}
+
+/// Create ZIP path for a file relative to the mod root
+fn create_zip_path(path: &Path, mod_root: &Path, top: &str) -> Option<String> {
+    let rel = path.strip_prefix(mod_root).ok()?;
+
     let mut zip_path = PathBuf::from(top);
     zip_path.push(rel);
     
@@ -68,16 +224,6 @@ fn create_zip_path(path: &Path, mod_root: &Path, top: &str, excludes: &[&str]) -
     Some(zip_path.to_string_lossy().replace('\\', "/"))
 }
 
-/// Check if a relative path should be excluded based on its first component
-fn should_exclude_path(rel_path: &Path, excludes: &[&str]) -> bool {
-    rel_path
-        .components()
-        .next()
-        .and_then(|c| c.as_os_str().to_str())
-        .map(|first| excludes.contains(&first))
-        .unwrap_or(false)
-}
-
 /// Add a directory to the ZIP archive
 fn add_directory_to_zip<W: Write + Seek>(
     zip: &mut zip::ZipWriter<W>,
@@ -111,6 +257,7 @@ fn add_default_thumbnail_if_missing<W: Write + Seek>(
     submod_root: &Path,
     default_thumb: Option<&[u8]>,
     top: &str,
+    opts: FileOptions<()>,
     config: &BuildConfig,
 ) -> Result<()> {
     // If mod already has thumbnail or no default provided, do nothing
@@ -119,7 +266,6 @@ fn add_default_thumbnail_if_missing<W: Write + Seek>(
     }
 
     let bytes = default_thumb.unwrap();
-    let opts: FileOptions<()> = FileOptions::default().compression_method(CompressionMethod::Deflated);
     let thumbnail_path = format!("{}/thumbnail.png", top);
     
     zip.start_file(&thumbnail_path, opts)?;
@@ -127,4 +273,20 @@ fn add_default_thumbnail_if_missing<W: Write + Seek>(
 
     config.log(&format!("🔧 Injected default thumbnail into {}", top));
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(time: DateTime) -> (u16, u8, u8, u8, u8, u8) {
+        (time.year(), time.month(), time.day(), time.hour(), time.minute(), time.second())
+    }
+
+    #[test]
+    fn unix_times_convert_and_clamp_to_the_zip_range() {
+        assert_eq!(parts(zip_datetime_from_unix(1_700_000_000)), (2023, 11, 14, 22, 13, 20));
+        assert_eq!(parts(zip_datetime_from_unix(0)), (1980, 1, 1, 0, 0, 0));
+        assert_eq!(parts(zip_datetime_from_unix(-86_400)), (1980, 1, 1, 0, 0, 0));
+        assert_eq!(parts(zip_datetime_from_unix(i64::MAX)), (2107, 12, 31, 23, 59, 58));
+    }
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use std::fs;
+
+use common::{cargo_factorio, write_mod, TempDir};
+
+/// Entry names of a zip, in archive order
+fn zip_entries(path: &std::path::Path) -> Vec<String> {
+    let archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    archive.file_names().map(str::to_string).collect()
+}
+
+#[test]
+fn parent_factorioignore_applies_without_git() {
+    let dir = TempDir::new("build-parent-ignore");
+    fs::write(dir.join(".factorioignore"), "*.xcf\n").unwrap();
+    write_mod(&dir.join("m"), "m", &["base"]);
+    fs::write(dir.join("m/data.lua"), "").unwrap();
+    fs::write(dir.join("m/art.xcf"), "").unwrap();
+
+    let output = cargo_factorio(&dir, &["build", "--no-gitignore"], &[]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let entries = zip_entries(&dir.join("build/m_1.0.0.zip"));
+    assert!(entries.contains(&"m_1.0.0/data.lua".to_string()), "{:?}", entries);
+    assert!(!entries.iter().any(|e| e.ends_with(".xcf")), "{:?}", entries);
+}
+
+#[test]
+fn failed_build_leaves_no_zip() {
+    let dir = TempDir::new("build-failed");
+    write_mod(&dir.join("m"), "m", &["base"]);
+    assert!(cargo_factorio(&dir, &["build"], &[]).status.success());
+    assert!(dir.join("build/m_1.0.0.zip").is_file());
+
+    let output = cargo_factorio(&dir, &["build", "--reproducible"], &[("SOURCE_DATE_EPOCH", "abc")]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("SOURCE_DATE_EPOCH"));
+    let leftovers: Vec<_> = fs::read_dir(dir.join("build")).unwrap().filter_map(Result::ok).map(|e| e.file_name()).collect();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+}
+
+#[test]
+fn stdout_lists_only_zip_paths() {
+    let dir = TempDir::new("build-stdout");
+    write_mod(&dir.join("core"), "core", &["base"]);
+    write_mod(&dir.join("planets"), "planets", &["base", "core"]);
+
+    let output = cargo_factorio(&dir, &["build", "--verbose"], &[]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "build/core_1.0.0.zip\nbuild/planets_1.0.0.zip\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("📦 Built build/core_1.0.0.zip"));
+}
+
+#[test]
+fn factorioignore_patterns_apply_at_every_depth() {
+    let dir = TempDir::new("build-factorioignore");
+    let m = dir.join("m");
+    write_mod(&m, "m", &["base"]);
+    fs::write(m.join(".factorioignore"), "*.xcf\n!keep.xcf\ndocs/\n/notes.txt\n").unwrap();
+    for file in ["data.lua", "art.xcf", "keep.xcf", "docs/guide.md", "notes.txt", "sub/notes.txt", "sub/docs", "sub/art.xcf", "sub/scratch.lua"] {
+        fs::create_dir_all(m.join(file).parent().unwrap()).unwrap();
+        fs::write(m.join(file), "").unwrap();
+    }
+    fs::write(m.join("sub/.factorioignore"), "scratch.lua\n").unwrap();
+
+    let output = cargo_factorio(&dir, &["build", "--no-gitignore"], &[]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let mut files: Vec<_> = zip_entries(&dir.join("build/m_1.0.0.zip"))
+        .into_iter()
+        .filter_map(|e| e.strip_prefix("m_1.0.0/").filter(|rel| !rel.is_empty() && !rel.ends_with('/')).map(str::to_string))
+        .collect();
+    files.sort();
+    assert_eq!(files, ["data.lua", "info.json", "keep.xcf", "sub/docs", "sub/notes.txt"]);
+}
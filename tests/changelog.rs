@@ -0,0 +1,46 @@
+mod common;
+
+use std::fs;
+
+use common::{cargo_factorio, write_mod, TempDir};
+
+const CHANGELOG: &str = "---------------------------------------------------------------------------------------------------
+Version: 1.0.0
+Date: 2024-05-01
+  Features:
+    - Added planets.
+---------------------------------------------------------------------------------------------------
+Version: 0.9.0
+  Bugfixes:
+    - Fixed a crash.
+";
+
+#[test]
+fn show_prints_the_info_json_version_by_default() {
+    let dir = TempDir::new("changelog-show");
+    write_mod(&dir.join("m"), "m", &["base"]);
+    fs::write(dir.join("m/changelog.txt"), CHANGELOG).unwrap();
+
+    let output = cargo_factorio(&dir, &["changelog", "show"], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "### Features\n\n- Added planets.\n");
+
+    let output = cargo_factorio(&dir, &["changelog", "show", "0.9.0", "--format", "text"], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Bugfixes:\n  - Fixed a crash.\n");
+
+    let output = cargo_factorio(&dir, &["changelog", "show", "./m"], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "### Features\n\n- Added planets.\n");
+}
+
+#[test]
+fn show_fails_for_a_missing_version() {
+    let dir = TempDir::new("changelog-missing");
+    write_mod(&dir, "m", &["base"]);
+    fs::write(dir.join("changelog.txt"), CHANGELOG).unwrap();
+
+    let output = cargo_factorio(&dir, &["changelog", "show", "2.0.0"], &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("has no section for version 2.0.0"));
+}
@@ -0,0 +1,137 @@
+//! Helpers shared by the integration tests: a throwaway workspace and a stub mod portal.
+
+// Every test crate compiles this module but uses only part of it
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[path = "../../src/test_support.rs"]
+mod test_support;
+pub use test_support::{write_mod, TempDir};
+
+/// One request the stub portal received
+pub struct Request {
+    pub method: String,
+    /// Path including the query string
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+}
+
+/// A canned reply for one path
+pub struct Route {
+    pub path: String,
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Route {
+    pub fn json(path: &str, status: u16, body: &str) -> Self {
+        Self { path: path.to_string(), status, content_type: "application/json", body: body.as_bytes().to_vec() }
+    }
+
+    pub fn bytes(path: &str, body: &[u8]) -> Self {
+        Self { path: path.to_string(), status: 200, content_type: "application/octet-stream", body: body.to_vec() }
+    }
+}
+
+/// An HTTP server on a free local port answering each request from a fixed route table; unknown paths get a 404.
+/// `{stub}` in a JSON reply is replaced with the server's own URL.
+pub struct StubPortal {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl StubPortal {
+    pub fn start(routes: Vec<Route>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&requests);
+        let routes: Vec<Route> = routes
+            .into_iter()
+            .map(|route| match route.content_type {
+                "application/json" => Route { body: String::from_utf8(route.body).unwrap().replace("{stub}", &url).into_bytes(), ..route },
+                _ => route,
+            })
+            .collect();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let Some(request) = read_request(&mut stream) else { continue };
+                let route_path = request.path.split('?').next().unwrap_or_default();
+                let reply = routes.iter().find(|r| r.path == route_path);
+                let (status, content_type, body) = match reply {
+                    Some(route) => (route.status, route.content_type, route.body.as_slice()),
+                    None => (404, "application/json", br#"{"message": "Not found"}"#.as_slice()),
+                };
+                let head = format!(
+                    "HTTP/1.1 {} Stub\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    content_type,
+                    body.len()
+                );
+                log.lock().unwrap().push(request);
+                let _ = stream.write_all(head.as_bytes()).and_then(|_| stream.write_all(body));
+            }
+        });
+        Self { url, requests }
+    }
+
+    /// Requests received so far whose path (without query) is `path`
+    pub fn requests_to(&self, path: &str) -> Vec<Request> {
+        let mut requests = self.requests.lock().unwrap();
+        let (matching, rest) = requests.drain(..).partition(|r: &Request| r.path.split('?').next() == Some(path));
+        *requests = rest;
+        matching
+    }
+}
+
+fn read_request(stream: &mut impl Read) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let (method, path) = (parts.next()?.to_string(), parts.next()?.to_string());
+
+    let mut headers = Vec::new();
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).ok()?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header.split_once(':')?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let length = headers.iter().find(|(n, _)| n.eq_ignore_ascii_case("content-length")).and_then(|(_, v)| v.parse().ok()).unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+    Some(Request { method, path, headers, body })
+}
+
+/// Run the binary in `dir` with a private home and no portal settings leaking in from the environment
+pub fn cargo_factorio(dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_cargo-factorio"));
+    command.current_dir(dir).args(args).env("HOME", dir).env("RUST_BACKTRACE", "0");
+    for var in ["FACTORIO_MODS_DIR", "FACTORIO_PORTAL_URL", "FACTORIO_API_KEY", "FACTORIO_USERNAME", "FACTORIO_TOKEN"] {
+        command.env_remove(var);
+    }
+    command.envs(env.iter().copied());
+    command.output().unwrap()
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use std::fs;
+
+use common::{cargo_factorio, write_mod, Route, StubPortal, TempDir};
+
+const LOGIN: &[(&str, &str)] = &[("FACTORIO_USERNAME", "tester"), ("FACTORIO_TOKEN", "secret")];
+
+/// Portal listing of `my lib` 1.0.0, whose download claims to have `sha1`
+fn listing(sha1: &str) -> String {
+    serde_json::json!({
+        "name": "my lib",
+        "releases": [{
+            "download_url": "/download/my-lib/1",
+            "file_name": "my lib_1.0.0.zip",
+            "info_json": { "factorio_version": "2.0", "dependencies": ["base"] },
+            "version": "1.0.0",
+            "sha1": sha1,
+        }],
+    })
+    .to_string()
+}
+
+#[test]
+fn fetches_missing_dependency_with_encoded_name() {
+    let zip = b"not really a zip";
+    let sha1 = sha1_smol::Sha1::from(zip).digest().to_string();
+    let portal = StubPortal::start(vec![
+        Route::json("/api/mods/my%20lib/full", 200, &listing(&sha1)),
+        Route::bytes("/download/my-lib/1", zip),
+    ]);
+    let dir = TempDir::new("deps-fetch");
+    write_mod(&dir.join("app"), "app", &["base", "my lib >= 1.0.0"]);
+    let mods_dir = dir.join("mods");
+
+    let output = cargo_factorio(&dir, &["deps", "fetch", "--mods-dir", mods_dir.to_str().unwrap(), "--portal-url", &portal.url], LOGIN);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read(mods_dir.join("my lib_1.0.0.zip")).unwrap(), zip);
+    assert_eq!(portal.requests_to("/api/mods/my%20lib/full").len(), 1);
+    let downloads = portal.requests_to("/download/my-lib/1");
+    assert_eq!(downloads.len(), 1);
+    assert!(downloads[0].path.contains("username=tester") && downloads[0].path.contains("token=secret"));
+}
+
+#[test]
+fn rejects_download_with_wrong_checksum() {
+    let portal = StubPortal::start(vec![
+        Route::json("/api/mods/my%20lib/full", 200, &listing("0000000000000000000000000000000000000000")),
+        Route::bytes("/download/my-lib/1", b"tampered"),
+    ]);
+    let dir = TempDir::new("deps-fetch-sha1");
+    write_mod(&dir.join("app"), "app", &["base", "my lib"]);
+    let mods_dir = dir.join("mods");
+
+    let output = cargo_factorio(&dir, &["deps", "fetch", "--mods-dir", mods_dir.to_str().unwrap(), "--portal-url", &portal.url], LOGIN);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Checksum mismatch"));
+    let leftovers: Vec<_> = fs::read_dir(&mods_dir).unwrap().filter_map(Result::ok).map(|e| e.file_name()).collect();
+    assert!(leftovers.iter().all(|name| !name.to_string_lossy().starts_with("my lib")), "{:?}", leftovers);
+}
+
+#[test]
+fn keeps_checkout_of_fetched_dependency() {
+    let zip = b"not really a zip";
+    let sha1 = sha1_smol::Sha1::from(zip).digest().to_string();
+    let portal = StubPortal::start(vec![
+        Route::json("/api/mods/my%20lib/full", 200, &listing(&sha1)),
+        Route::bytes("/download/my-lib/1", zip),
+    ]);
+    let dir = TempDir::new("deps-fetch-checkout");
+    write_mod(&dir.join("app"), "app", &["base", "my lib >= 1.0.0"]);
+    // Outside the workspace, so the checkout isn't discovered as a workspace mod
+    let mods_dir = TempDir::new("deps-fetch-checkout-mods");
+    fs::create_dir_all(mods_dir.join("my lib/.git")).unwrap();
+    fs::write(mods_dir.join("my lib/info.json"), r#"{"name": "my lib", "version": "0.5.0"}"#).unwrap();
+    fs::write(mods_dir.join("my lib_0.4.0.zip"), "old").unwrap();
+
+    let output = cargo_factorio(&dir, &["deps", "fetch", "--mods-dir", mods_dir.to_str().unwrap(), "--portal-url", &portal.url], LOGIN);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read(mods_dir.join("my lib_1.0.0.zip")).unwrap(), zip);
+    assert!(!mods_dir.join("my lib_0.4.0.zip").exists());
+    assert!(mods_dir.join("my lib/.git").is_dir());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("not created by cargo factorio"));
+}
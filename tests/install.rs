@@ -0,0 +1,142 @@
+mod common;
+
+use std::fs;
+
+use common::{cargo_factorio, write_mod, TempDir};
+
+#[test]
+fn install_keeps_folders_it_did_not_create() {
+    let dir = TempDir::new("install-keep");
+    write_mod(&dir.join("a"), "a", &["base"]);
+    let mods_dir = dir.join("mods");
+    fs::create_dir_all(mods_dir.join("a_0.9.0")).unwrap();
+    fs::write(mods_dir.join("a_0.9.0/work.lua"), "-- unsaved work").unwrap();
+    fs::write(mods_dir.join("a_0.8.0.zip"), "old").unwrap();
+    let mods_dir_arg = mods_dir.to_str().unwrap();
+
+    let output = cargo_factorio(&dir, &["install", "--mods-dir", mods_dir_arg], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(mods_dir.join("a_1.0.0.zip").is_file());
+    assert!(!mods_dir.join("a_0.8.0.zip").exists());
+    assert!(mods_dir.join("a_0.9.0/work.lua").is_file());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("not created by cargo factorio"));
+
+    let output = cargo_factorio(&dir, &["install", "--mods-dir", mods_dir_arg, "--force"], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!mods_dir.join("a_0.9.0").exists());
+}
+
+#[test]
+fn uninstall_removes_installs_and_mod_list_entry() {
+    let dir = TempDir::new("uninstall");
+    write_mod(&dir.join("a"), "a", &["base"]);
+    let mods = TempDir::new("uninstall-mods");
+    let mods_dir = mods.to_str().unwrap();
+    fs::write(mods.join("mod-list.json"), r#"{"mods": [{"name": "base", "enabled": true}, {"name": "a", "enabled": true}]}"#).unwrap();
+    assert!(cargo_factorio(&dir, &["install", "--mods-dir", mods_dir], &[]).status.success());
+    fs::write(mods.join("a_0.9.0.zip"), "old").unwrap();
+    write_mod(&mods.join("a_0.8.0"), "a", &["base"]);
+
+    let output = cargo_factorio(&dir, &["uninstall", "--mods-dir", mods_dir], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!mods.join("a_1.0.0.zip").exists());
+    assert!(!mods.join("a_0.9.0.zip").exists());
+    assert!(mods.join("a_0.8.0/info.json").is_file());
+    let mod_list = fs::read_to_string(mods.join("mod-list.json")).unwrap();
+    assert!(!mod_list.contains("\"a\""), "{}", mod_list);
+    assert!(mod_list.contains("\"base\""), "{}", mod_list);
+
+    let output = cargo_factorio(&dir, &["uninstall", "--mods-dir", mods_dir, "--force"], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!mods.join("a_0.8.0").exists());
+    assert!(dir.join("a/info.json").is_file());
+}
+
+#[test]
+fn install_enable_turns_on_the_mod_and_its_dependencies() {
+    let dir = TempDir::new("install-enable");
+    write_mod(&dir.join("a"), "a", &["base", "lib", "? extra"]);
+    let mods = TempDir::new("install-enable-mods");
+    let mods_dir = mods.to_str().unwrap();
+    write_mod(&mods.join("lib"), "lib", &["base"]);
+    let mod_list = r#"{"mods": [{"name": "base", "enabled": true}, {"name": "a", "enabled": false}, {"name": "lib", "enabled": false}, {"name": "extra", "enabled": false}]}"#;
+    fs::write(mods.join("mod-list.json"), mod_list).unwrap();
+
+    let output = cargo_factorio(&dir, &["install", "--mods-dir", mods_dir, "--enable"], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let enabled = |list: &serde_json::Value, name: &str| {
+        let entry = list["mods"].as_array().unwrap().iter().find(|m| m["name"] == name).unwrap();
+        entry["enabled"].clone()
+    };
+    let list: serde_json::Value = serde_json::from_str(&fs::read_to_string(mods.join("mod-list.json")).unwrap()).unwrap();
+    assert_eq!(enabled(&list, "a"), true);
+    assert_eq!(enabled(&list, "lib"), true);
+    assert_eq!(enabled(&list, "extra"), false);
+
+    let output = cargo_factorio(&dir, &["disable", "--mods-dir", mods_dir], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let list: serde_json::Value = serde_json::from_str(&fs::read_to_string(mods.join("mod-list.json")).unwrap()).unwrap();
+    assert_eq!(enabled(&list, "a"), false);
+    assert_eq!(enabled(&list, "lib"), true);
+}
+
+#[test]
+fn install_deploys_to_every_target() {
+    let dir = TempDir::new("install-targets");
+    write_mod(&dir.join("a"), "a", &["base"]);
+    let server = TempDir::new("install-targets-server");
+    let client = TempDir::new("install-targets-client");
+    fs::write(client.join("config-path.cfg"), "config-path=__PATH__executable__/../../config\nuse-system-read-write-data-directories=false\n").unwrap();
+    fs::create_dir_all(client.join("config")).unwrap();
+    fs::write(client.join("config/config.ini"), "[path]\nwrite-data=__PATH__executable__/../../data\n").unwrap();
+    let config = format!("[targets.server]\nmods_dir = {:?}\n\n[targets.client]\ninstall_dir = {:?}\n", server.to_str().unwrap(), client.to_str().unwrap());
+    fs::write(dir.join("factorio.toml"), config).unwrap();
+
+    let output = cargo_factorio(&dir, &["install", "--target", "server", "--target", "client"], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(server.join("a_1.0.0.zip").is_file());
+    assert!(client.join("data/mods/a_1.0.0.zip").is_file());
+
+    let env_mods = TempDir::new("install-targets-env");
+    let output = cargo_factorio(&dir, &["install"], &[("FACTORIO_MODS_DIR", env_mods.to_str().unwrap())]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(env_mods.join("a_1.0.0.zip").is_file());
+}
+
+#[test]
+fn install_checks_the_target_game_version() {
+    let dir = TempDir::new("install-game");
+    write_mod(&dir.join("a"), "a", &["base"]);
+    let game = TempDir::new("install-game-dir");
+    fs::create_dir_all(game.join("data/base")).unwrap();
+    fs::write(game.join("data/base/info.json"), r#"{"name": "base", "version": "1.1.110"}"#).unwrap();
+    fs::write(dir.join("factorio.toml"), format!("[targets.old]\ninstall_dir = {:?}\n", game.to_str().unwrap())).unwrap();
+
+    let output = cargo_factorio(&dir, &["install", "--target", "old"], &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`a` is made for Factorio 2.0 but the game is 1.1.110 (target old)"));
+    assert!(!game.join("mods/a_1.0.0.zip").exists());
+
+    let output = cargo_factorio(&dir, &["install", "--target", "old", "--force"], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("is made for Factorio 2.0"));
+    assert!(game.join("mods/a_1.0.0.zip").is_file());
+}
+
+#[test]
+fn install_reports_dependencies_the_mods_folder_lacks() {
+    let dir = TempDir::new("install-deps");
+    write_mod(&dir.join("a"), "a", &["base", "lib >= 2.0.0", "gone", "! rival"]);
+    let mods = TempDir::new("install-deps-mods");
+    fs::write(mods.join("lib_1.0.0.zip"), "").unwrap();
+    write_mod(&mods.join("gone"), "gone", &["base"]);
+    write_mod(&mods.join("rival"), "rival", &["base"]);
+    fs::write(mods.join("mod-list.json"), r#"{"mods": [{"name": "base", "enabled": true}, {"name": "gone", "enabled": false}]}"#).unwrap();
+
+    let output = cargo_factorio(&dir, &["install", "--mods-dir", mods.to_str().unwrap()], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("`a` depends on `lib >= 2.0.0` but 1.0.0 is installed"), "{}", stdout);
+    assert!(stdout.contains("`a` requires `gone`, which is disabled"), "{}", stdout);
+    assert!(stdout.contains("`a` is incompatible with `rival`, which is installed and enabled"), "{}", stdout);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use std::fs;
+
+use common::{cargo_factorio, write_mod, TempDir};
+
+#[test]
+fn links_mod_into_mods_dir() {
+    let dir = TempDir::new("link");
+    write_mod(&dir.join("a"), "a", &["base"]);
+    let mods = TempDir::new("link-mods");
+    fs::write(mods.join("a_0.9.0.zip"), "old").unwrap();
+
+    let output = cargo_factorio(&dir, &["link", "--mods-dir", mods.to_str().unwrap()], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!mods.join("a_0.9.0.zip").exists());
+    let link = mods.join("a");
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+    assert_eq!(fs::canonicalize(&link).unwrap(), fs::canonicalize(dir.join("a")).unwrap());
+}
+
+#[test]
+fn link_rejects_build_only_options() {
+    let dir = TempDir::new("link-args");
+    write_mod(&dir, "a", &["base"]);
+
+    for args in [&["link", "--out-dir", "out"][..], &["link", "--reproducible"]] {
+        let output = cargo_factorio(&dir, args, &[]);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("unexpected argument"));
+    }
+}
+
+#[test]
+fn unlink_removes_the_link_but_not_the_source() {
+    let dir = TempDir::new("unlink");
+    write_mod(&dir.join("a"), "a", &["base"]);
+    let mods = TempDir::new("unlink-mods");
+    let mods_dir = mods.to_str().unwrap();
+
+    assert!(cargo_factorio(&dir, &["link", "--mods-dir", mods_dir], &[]).status.success());
+    let output = cargo_factorio(&dir, &["unlink", "--mods-dir", mods_dir], &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(fs::symlink_metadata(mods.join("a")).is_err());
+    assert!(dir.join("a/info.json").is_file());
+
+    let output = cargo_factorio(&dir, &["unlink", "--mods-dir", mods_dir], &[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("a is not linked"));
+}
+
+#[test]
+fn link_refuses_to_replace_a_foreign_folder() {
+    let dir = TempDir::new("link-foreign");
+    write_mod(&dir.join("a"), "a", &["base"]);
+    let mods = TempDir::new("link-foreign-mods");
+    write_mod(&mods.join("a"), "a", &["base"]);
+
+    let output = cargo_factorio(&dir, &["link", "--mods-dir", mods.to_str().unwrap()], &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("was not created by `cargo factorio link`"));
+    assert!(!fs::symlink_metadata(mods.join("a")).unwrap().file_type().is_symlink());
+
+    let output = cargo_factorio(&dir, &["unlink", "--mods-dir", mods.to_str().unwrap()], &[]);
+    assert!(output.status.success());
+    assert!(mods.join("a/info.json").is_file());
+}
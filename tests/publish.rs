@@ -0,0 +1,65 @@
+mod common;
+
+use std::fs;
+
+use common::{cargo_factorio, write_mod, Route, StubPortal, TempDir};
+
+const API_KEY: &[(&str, &str)] = &[("FACTORIO_API_KEY", "test-key")];
+
+#[test]
+fn uploads_release_and_readme() {
+    let portal = StubPortal::start(vec![
+        Route::json("/api/v2/mods/releases/init_upload", 200, r#"{"upload_url": "{stub}/upload/42"}"#),
+        Route::json("/upload/42", 200, r#"{"success": true}"#),
+        Route::json("/api/v2/mods/edit_details", 200, r#"{"success": true}"#),
+    ]);
+    let dir = TempDir::new("publish");
+    write_mod(&dir, "app", &["base"]);
+    fs::write(dir.join("README.md"), "# App\n\nDoes app things.\n\n## Usage\n\nInstall it.\n").unwrap();
+
+    let output = cargo_factorio(&dir, &["publish", "--portal-url", &portal.url, "--readme"], API_KEY);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let init = portal.requests_to("/api/v2/mods/releases/init_upload");
+    assert_eq!(init.len(), 1);
+    assert_eq!(init[0].method, "POST");
+    assert_eq!(init[0].header("Authorization"), Some("Bearer test-key"));
+    assert_eq!(init[0].body, b"mod=app");
+
+    let upload = portal.requests_to("/upload/42");
+    assert_eq!(upload.len(), 1);
+    let content_type = upload[0].header("Content-Type").unwrap();
+    let boundary = content_type.strip_prefix("multipart/form-data; boundary=").expect(content_type);
+    let zip = fs::read(dir.join("build/app_1.0.0.zip")).unwrap();
+    let mut expected = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"app_1.0.0.zip\"\r\nContent-Type: application/zip\r\n\r\n",
+        boundary
+    )
+    .into_bytes();
+    expected.extend_from_slice(&zip);
+    expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    assert!(upload[0].body == expected, "unexpected multipart body");
+
+    let details = portal.requests_to("/api/v2/mods/edit_details");
+    assert_eq!(details.len(), 1);
+    assert_eq!(details[0].header("Authorization"), Some("Bearer test-key"));
+    let form = String::from_utf8_lossy(&details[0].body);
+    assert!(form.contains("summary=Does+app+things.") || form.contains("summary=Does%20app%20things."), "{}", form);
+}
+
+#[test]
+fn reports_portal_error_message() {
+    let portal = StubPortal::start(vec![Route::json(
+        "/api/v2/mods/releases/init_upload",
+        403,
+        r#"{"error": "InvalidApiKey", "message": "Missing or invalid API key"}"#,
+    )]);
+    let dir = TempDir::new("publish-denied");
+    write_mod(&dir, "app", &["base"]);
+
+    let output = cargo_factorio(&dir, &["publish", "--portal-url", &portal.url], API_KEY);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Missing or invalid API key"));
+    assert!(portal.requests_to("/upload/42").is_empty());
+}